@@ -81,7 +81,7 @@ pub enum BlockDriveError {
     #[msg("Vault master not found")]
     VaultMasterNotFound,
 
-    #[msg("Maximum number of shards (10) reached - cannot store more than 1000 files")]
+    #[msg("Maximum number of shards (250) reached - cannot store more than 25000 files")]
     MaxShardsReached,
 
     #[msg("Invalid shard index - must create shards sequentially")]
@@ -96,7 +96,7 @@ pub enum BlockDriveError {
     #[msg("No available shard to store file - create a new shard first")]
     NoAvailableShard,
 
-    #[msg("Vault index is full - maximum 1000 files supported")]
+    #[msg("Vault index page is full - maximum 1000 files per page")]
     IndexFull,
 
     #[msg("File ID already exists in vault")]
@@ -104,4 +104,151 @@ pub enum BlockDriveError {
 
     #[msg("Slot index is out of bounds")]
     InvalidSlotIndex,
+
+    // =========================================================================
+    // FILE LIFECYCLE ERRORS
+    // =========================================================================
+
+    #[msg("File is not archived")]
+    FileNotArchived,
+
+    #[msg("File has active delegations - revoke them first")]
+    FileHasDelegations,
+
+    #[msg("Vault still contains files")]
+    VaultNotEmpty,
+
+    #[msg("A migrated file's delegations and versions must be passed as (legacy, new) pairs")]
+    InvalidMigrationAccounts,
+
+    // =========================================================================
+    // DIRECTORY PAGE ERRORS
+    // =========================================================================
+
+    #[msg("Directory page not found - create the next directory page first")]
+    DirectoryPageNotFound,
+
+    #[msg("Directory or index page does not cover this shard")]
+    InvalidDirectoryPage,
+
+    // =========================================================================
+    // SHARD LIFECYCLE ERRORS
+    // =========================================================================
+
+    #[msg("Shard is already archived")]
+    ShardArchived,
+
+    #[msg("Shard still contains files")]
+    ShardNotEmpty,
+
+    #[msg("Vault master still has open shards - close them first")]
+    ShardsStillOpen,
+
+    #[msg("Overflow directory and index pages must all be provided")]
+    MissingDirectoryPages,
+
+    #[msg("The active shard can only be closed after every other shard")]
+    ActiveShardOpen,
+
+    #[msg("Shard or index account uses the pre-zero-copy layout - migrate it first")]
+    LegacyAccountLayout,
+
+    // =========================================================================
+    // GUARDIAN RECOVERY ERRORS
+    // =========================================================================
+
+    #[msg("Guardians must be 1-10 unique wallets, excluding the owner")]
+    InvalidGuardianSet,
+
+    #[msg("Guardian threshold must be between 1 and the number of guardians")]
+    InvalidGuardianThreshold,
+
+    #[msg("Recovery delay is below the minimum of 24 hours")]
+    RecoveryDelayTooShort,
+
+    #[msg("Signer is not a guardian of this vault")]
+    NotAGuardian,
+
+    #[msg("Guardian has already approved this recovery")]
+    RecoveryAlreadyApproved,
+
+    #[msg("A recovery is already pending for this vault")]
+    RecoveryPending,
+
+    #[msg("Recovery threshold not reached or timelock still active")]
+    RecoveryNotExecutable,
+
+    #[msg("New owner must be a different, non-default wallet")]
+    InvalidNewOwner,
+
+    #[msg("Either the UserVault or the UserVaultMaster must be provided")]
+    VaultAccountRequired,
+
+    // =========================================================================
+    // KEY ROTATION ERRORS
+    // =========================================================================
+
+    #[msg("File is already encrypted under the current key epoch")]
+    FileKeyEpochCurrent,
+
+    #[msg("Too many accounts passed in a single batch")]
+    BatchTooLarge,
+
+    // =========================================================================
+    // FREEZE AUTHORITY ERRORS
+    // =========================================================================
+
+    #[msg("Signer is neither the config authority nor a guardian of this vault")]
+    NotFreezeAuthority,
+
+    #[msg("Invalid freeze reason")]
+    InvalidFreezeReason,
+
+    #[msg("Vault cannot be unfrozen until the timelock has elapsed")]
+    UnfreezeTimelockActive,
+
+    // =========================================================================
+    // FILE VERSIONING ERRORS
+    // =========================================================================
+
+    #[msg("File still has version snapshots; prune them first")]
+    FileHasVersions,
+
+    #[msg("File version does not belong to this file")]
+    InvalidFileVersion,
+
+    // =========================================================================
+    // DELEGATION LIFECYCLE ERRORS
+    // =========================================================================
+
+    #[msg("Delegation has already been accepted")]
+    DelegationAlreadyAccepted,
+
+    #[msg("Re-share depth limit reached")]
+    ReshareDepthExceeded,
+
+    #[msg("Sub-delegation cannot exceed the parent's permission level or expiry")]
+    ReshareExceedsParent,
+
+    #[msg("A parent delegation is missing, revoked or no longer allows re-sharing")]
+    DelegationChainBroken,
+
+    #[msg("Delegations must be passed as (delegation, grantor) pairs of this file")]
+    InvalidDelegationAccounts,
+
+    #[msg("File has reached the maximum number of delegations")]
+    DelegationLimitReached,
+
+    // =========================================================================
+    // CLEANUP CRANK ERRORS
+    // =========================================================================
+
+    #[msg("Delegation has not expired yet")]
+    DelegationNotExpired,
+
+    #[msg("Session is still active and has not expired")]
+    SessionStillActive,
+
+    #[msg("Cleanup tip exceeds the maximum allowed")]
+    CleanupTipTooHigh,
 }
@@ -26,7 +26,7 @@ pub struct ShardCreated {
     pub vault_master: Pubkey,
     /// The new VaultShard PDA address
     pub vault_shard: Pubkey,
-    /// The shard index (0-9)
+    /// The shard index (0-249)
     pub shard_index: u8,
     /// The wallet owner
     pub owner: Pubkey,
@@ -34,6 +34,60 @@ pub struct ShardCreated {
     pub timestamp: i64,
 }
 
+/// Emitted when a shard is archived (read-only)
+#[event]
+pub struct ShardArchived {
+    /// The parent VaultMaster
+    pub vault_master: Pubkey,
+    /// The archived VaultShard PDA
+    pub vault_shard: Pubkey,
+    /// The shard index (0-249)
+    pub shard_index: u8,
+    /// Archive timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when an empty shard is closed and its rent reclaimed
+#[event]
+pub struct ShardClosed {
+    /// The parent VaultMaster
+    pub vault_master: Pubkey,
+    /// The closed VaultShard PDA
+    pub vault_shard: Pubkey,
+    /// The shard index (0-249)
+    pub shard_index: u8,
+    /// Close timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a VaultMaster and its index/directory pages are closed
+#[event]
+pub struct VaultMasterClosed {
+    /// The wallet owner
+    pub owner: Pubkey,
+    /// The closed VaultMaster PDA
+    pub vault_master: Pubkey,
+    /// Number of overflow directory pages closed alongside it
+    pub directory_pages: u8,
+    /// Close timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when an overflow directory page is created
+#[event]
+pub struct DirectoryPageCreated {
+    /// The parent VaultMaster
+    pub vault_master: Pubkey,
+    /// The new VaultDirectory PDA address
+    pub vault_directory: Pubkey,
+    /// The page index (1+)
+    pub page_index: u8,
+    /// The wallet owner
+    pub owner: Pubkey,
+    /// Creation timestamp
+    pub timestamp: i64,
+}
+
 /// Emitted when a file is registered to a shard
 #[event]
 pub struct FileRegisteredSharded {
@@ -59,6 +113,97 @@ pub struct FileRegisteredSharded {
     pub timestamp: i64,
 }
 
+/// Emitted when a file is deleted from a shard
+#[event]
+pub struct FileDeletedSharded {
+    /// The VaultMaster
+    pub vault_master: Pubkey,
+    /// The VaultShard the file was removed from
+    pub vault_shard: Pubkey,
+    /// The closed FileRecord PDA
+    pub file_record: Pubkey,
+    /// The unique file ID
+    pub file_id: [u8; 16],
+    /// Shard index the file was stored in
+    pub shard_index: u8,
+    /// Slot index that was freed
+    pub slot_index: u8,
+    /// Original file size
+    pub file_size: u64,
+    /// Deletion timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a shard's file records are packed into a dense prefix
+#[event]
+pub struct ShardCompacted {
+    /// The VaultMaster
+    pub vault_master: Pubkey,
+    /// The compacted VaultShard
+    pub vault_shard: Pubkey,
+    /// Shard index (0-249)
+    pub shard_index: u8,
+    /// Number of file records that changed slot
+    pub files_moved: u8,
+    /// Compaction timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a file record is moved from one shard to another
+#[event]
+pub struct FileRebalanced {
+    /// The VaultMaster
+    pub vault_master: Pubkey,
+    /// The moved FileRecord PDA
+    pub file_record: Pubkey,
+    /// The unique file ID
+    pub file_id: [u8; 16],
+    /// Shard the file was moved out of
+    pub from_shard_index: u8,
+    /// Slot the file was moved out of
+    pub from_slot_index: u8,
+    /// Shard the file was moved into
+    pub to_shard_index: u8,
+    /// Slot the file was moved into
+    pub to_slot_index: u8,
+    /// Rebalance timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a legacy FileRecord is moved into sharded storage
+#[event]
+pub struct LegacyFileMigrated {
+    /// The legacy UserVault the file came from
+    pub vault: Pubkey,
+    /// The VaultMaster the file now belongs to
+    pub vault_master: Pubkey,
+    /// The closed legacy FileRecord PDA
+    pub legacy_file_record: Pubkey,
+    /// The new FileRecord PDA under the VaultMaster
+    pub file_record: Pubkey,
+    /// The unique file ID
+    pub file_id: [u8; 16],
+    /// Shard index where the file was placed
+    pub shard_index: u8,
+    /// Slot index within the shard
+    pub slot_index: u8,
+    /// Migration timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a fully migrated legacy UserVault is closed
+#[event]
+pub struct LegacyVaultMigrated {
+    /// The wallet owner
+    pub owner: Pubkey,
+    /// The closed legacy UserVault
+    pub vault: Pubkey,
+    /// The VaultMaster that replaced it
+    pub vault_master: Pubkey,
+    /// Completion timestamp
+    pub timestamp: i64,
+}
+
 /// Emitted when a Vault Index is created
 #[event]
 pub struct VaultIndexCreated {
@@ -72,6 +217,131 @@ pub struct VaultIndexCreated {
     pub timestamp: i64,
 }
 
+// ============================================================================
+// CONFIG EVENTS
+// ============================================================================
+
+/// Emitted when the global config is initialized
+#[event]
+pub struct ConfigInitialized {
+    /// The VaultConfig PDA address
+    pub config: Pubkey,
+    /// The program authority
+    pub authority: Pubkey,
+    /// The fee collector wallet
+    pub fee_collector: Pubkey,
+    /// File registration fee (lamports)
+    pub file_registration_fee: u64,
+    /// Delegation creation fee (lamports)
+    pub delegation_fee: u64,
+    /// Maximum file size (bytes, 0 = unlimited)
+    pub max_file_size: u64,
+    /// Maximum files per vault (0 = unlimited)
+    pub max_files_per_vault: u64,
+    /// Creation timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when program fees are updated
+#[event]
+pub struct FeesUpdated {
+    /// The VaultConfig PDA address
+    pub config: Pubkey,
+    /// New file registration fee (lamports)
+    pub file_registration_fee: u64,
+    /// New delegation creation fee (lamports)
+    pub delegation_fee: u64,
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when file limits are updated
+#[event]
+pub struct LimitsUpdated {
+    /// The VaultConfig PDA address
+    pub config: Pubkey,
+    /// New maximum file size (bytes, 0 = unlimited)
+    pub max_file_size: u64,
+    /// New maximum files per vault (0 = unlimited)
+    pub max_files_per_vault: u64,
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when the program is paused or unpaused
+#[event]
+pub struct PauseStatusChanged {
+    /// The VaultConfig PDA address
+    pub config: Pubkey,
+    /// Whether the program is now paused
+    pub is_paused: bool,
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Kind of fee charged by the program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FeeType {
+    /// Charged by register_file / register_file_sharded
+    FileRegistration = 0,
+    /// Charged by create_delegation
+    Delegation = 1,
+}
+
+/// Emitted when a fee is transferred to the fee collector
+#[event]
+pub struct FeeCollected {
+    /// The wallet that paid the fee
+    pub payer: Pubkey,
+    /// The fee collector wallet
+    pub fee_collector: Pubkey,
+    /// The account the fee was charged for (FileRecord or Delegation)
+    pub reference: Pubkey,
+    /// Kind of fee
+    pub fee_type: FeeType,
+    /// Amount in lamports
+    pub amount: u64,
+    /// Collection timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when the fee collector wallet is changed
+#[event]
+pub struct FeeCollectorUpdated {
+    /// The VaultConfig PDA address
+    pub config: Pubkey,
+    /// Previous fee collector
+    pub old_fee_collector: Pubkey,
+    /// New fee collector
+    pub new_fee_collector: Pubkey,
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when the cleanup tip is changed
+#[event]
+pub struct CleanupTipUpdated {
+    /// The VaultConfig PDA address
+    pub config: Pubkey,
+    /// New tip in lamports
+    pub cleanup_tip: u64,
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when the cleanup tip pool is topped up
+#[event]
+pub struct CleanupTipsFunded {
+    /// The VaultConfig PDA address
+    pub config: Pubkey,
+    /// Wallet that funded the pool
+    pub funder: Pubkey,
+    /// Lamports added
+    pub amount: u64,
+    /// Funding timestamp
+    pub timestamp: i64,
+}
+
 // ============================================================================
 // SESSION DELEGATION EVENTS
 // ============================================================================
@@ -123,6 +393,23 @@ pub struct SessionDelegationExtended {
     pub timestamp: i64,
 }
 
+/// Emitted when an expired or revoked session is closed by a cranker
+#[event]
+pub struct ExpiredSessionClosed {
+    /// The SessionDelegation PDA address
+    pub session: Pubkey,
+    /// Session owner (received the rent)
+    pub owner: Pubkey,
+    /// Relayer the session was granted to
+    pub relayer: Pubkey,
+    /// Wallet that closed the session
+    pub cranker: Pubkey,
+    /// Lamports paid to the cranker
+    pub tip: u64,
+    /// Close timestamp
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct VaultCreated {
     pub owner: Pubkey,
@@ -130,9 +417,39 @@ pub struct VaultCreated {
     pub timestamp: i64,
 }
 
+/// Why a vault was frozen
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FreezeReason {
+    /// Frozen by the owner via freeze_vault / freeze_vault_sharded
+    OwnerRequest = 0,
+    /// Owner wallet reported as compromised
+    CompromisedWallet = 1,
+    /// Compliance or legal hold
+    Compliance = 2,
+    /// Any other reason, recorded off-chain
+    Other = 3,
+}
+
+impl FreezeReason {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(FreezeReason::OwnerRequest),
+            1 => Some(FreezeReason::CompromisedWallet),
+            2 => Some(FreezeReason::Compliance),
+            3 => Some(FreezeReason::Other),
+            _ => None,
+        }
+    }
+}
+
 #[event]
 pub struct VaultFrozen {
     pub vault: Pubkey,
+    /// Owner, config authority or guardian that froze the vault
+    pub frozen_by: Pubkey,
+    pub reason: FreezeReason,
+    /// Earliest time the owner can unfreeze
+    pub unfreeze_after: i64,
     pub timestamp: i64,
 }
 
@@ -142,9 +459,21 @@ pub struct VaultUnfrozen {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct VaultClosed {
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    /// FileRecords closed in force mode
+    pub files_closed: u64,
+    pub forced: bool,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct MasterKeyRotated {
     pub vault: Pubkey,
+    /// Epoch after the rotation
+    pub key_epoch: u32,
     pub timestamp: i64,
 }
 
@@ -166,6 +495,38 @@ pub struct FileStorageUpdated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FileMetadataUpdated {
+    pub vault: Pubkey,
+    pub file_record: Pubkey,
+    pub filename_hash: [u8; 32],
+    pub mime_type_hash: [u8; 32],
+    /// Owner, or the relayer acting through a session
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FileVersionCommitted {
+    pub vault: Pubkey,
+    pub file_record: Pubkey,
+    /// Snapshot holding the replaced content
+    pub file_version: Pubkey,
+    /// New head version number
+    pub version: u32,
+    pub file_size: u64,
+    pub encrypted_size: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FileVersionsPruned {
+    pub file_record: Pubkey,
+    pub versions_closed: u16,
+    pub retained_versions: u16,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct FileArchived {
     pub vault: Pubkey,
@@ -173,6 +534,13 @@ pub struct FileArchived {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FileUnarchived {
+    pub vault: Pubkey,
+    pub file_record: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct FileDeleted {
     pub vault: Pubkey,
@@ -188,6 +556,15 @@ pub struct FileAccessed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct FileReencrypted {
+    pub vault: Pubkey,
+    pub file_record: Pubkey,
+    pub previous_key_epoch: u32,
+    pub key_epoch: u32,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct DelegationCreated {
     pub file_record: Pubkey,
@@ -206,6 +583,47 @@ pub struct DelegationRevoked {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct DelegationAccepted {
+    pub file_record: Pubkey,
+    pub grantor: Pubkey,
+    pub grantee: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegationRejected {
+    pub file_record: Pubkey,
+    pub grantor: Pubkey,
+    pub grantee: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegationReshared {
+    pub file_record: Pubkey,
+    /// The re-sharing grantee's own delegation
+    pub parent_delegation: Pubkey,
+    pub grantor: Pubkey,
+    pub grantee: Pubkey,
+    pub permission_level: u8,
+    pub expires_at: i64,
+    /// Depth of the new delegation (1 = re-shared from an owner grant)
+    pub depth: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExpiredDelegationClosed {
+    pub delegation: Pubkey,
+    pub file_record: Pubkey,
+    /// Received the rent
+    pub grantor: Pubkey,
+    pub cranker: Pubkey,
+    pub tip: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct DelegationUpdated {
     pub file_record: Pubkey,
@@ -215,6 +633,147 @@ pub struct DelegationUpdated {
     pub timestamp: i64,
 }
 
+// ============================================================================
+// OWNERSHIP & RECOVERY EVENTS
+// ============================================================================
+
+/// Emitted when a vault's guardian set is created or replaced
+#[event]
+pub struct GuardiansUpdated {
+    /// The GuardianSet PDA
+    pub guardian_set: Pubkey,
+    /// Owner-independent vault id
+    pub vault_id: Pubkey,
+    /// Current vault owner
+    pub owner: Pubkey,
+    /// Number of guardians (N)
+    pub guardian_count: u8,
+    /// Approvals required (M)
+    pub threshold: u8,
+    /// Timelock after reaching the threshold (seconds)
+    pub recovery_delay: i64,
+    /// Update timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a guardian opens a recovery request
+#[event]
+pub struct RecoveryInitiated {
+    /// The GuardianSet PDA
+    pub guardian_set: Pubkey,
+    /// The RecoveryRequest PDA
+    pub recovery_request: Pubkey,
+    /// Owner-independent vault id
+    pub vault_id: Pubkey,
+    /// Guardian that opened the request
+    pub initiator: Pubkey,
+    /// Proposed new owner
+    pub new_owner: Pubkey,
+    /// Initiation timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a guardian approves a recovery request
+#[event]
+pub struct RecoveryApproved {
+    /// The RecoveryRequest PDA
+    pub recovery_request: Pubkey,
+    /// Approving guardian
+    pub guardian: Pubkey,
+    /// Approvals collected so far
+    pub approval_count: u8,
+    /// Earliest execution time (0 until the threshold is reached)
+    pub executable_at: i64,
+    /// Approval timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a recovery moves the vault to a new owner
+#[event]
+pub struct RecoveryExecuted {
+    /// The GuardianSet PDA
+    pub guardian_set: Pubkey,
+    /// Owner-independent vault id
+    pub vault_id: Pubkey,
+    /// Owner before recovery
+    pub old_owner: Pubkey,
+    /// Owner after recovery
+    pub new_owner: Pubkey,
+    /// Execution timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when the owner cancels a pending recovery
+#[event]
+pub struct RecoveryCancelled {
+    /// The GuardianSet PDA
+    pub guardian_set: Pubkey,
+    /// The closed RecoveryRequest PDA
+    pub recovery_request: Pubkey,
+    /// Owner that cancelled
+    pub owner: Pubkey,
+    /// Cancellation timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner proposes moving the vault to another wallet
+#[event]
+pub struct OwnerTransferProposed {
+    /// The OwnerTransfer PDA
+    pub owner_transfer: Pubkey,
+    /// Owner-independent vault id
+    pub vault_id: Pubkey,
+    /// Current owner
+    pub current_owner: Pubkey,
+    /// Proposed new owner
+    pub new_owner: Pubkey,
+    /// Proposal timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when the new owner accepts and the vault accounts move
+#[event]
+pub struct OwnerTransferAccepted {
+    /// Owner-independent vault id
+    pub vault_id: Pubkey,
+    /// UserVault moved (default if none)
+    pub vault: Pubkey,
+    /// UserVaultMaster moved (default if none)
+    pub vault_master: Pubkey,
+    /// Owner before the transfer
+    pub old_owner: Pubkey,
+    /// Owner after the transfer
+    pub new_owner: Pubkey,
+    /// Acceptance timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when the current owner withdraws a transfer proposal
+#[event]
+pub struct OwnerTransferCancelled {
+    /// The closed OwnerTransfer PDA
+    pub owner_transfer: Pubkey,
+    /// Owner-independent vault id
+    pub vault_id: Pubkey,
+    /// Owner that cancelled
+    pub owner: Pubkey,
+    /// Cancellation timestamp
+    pub timestamp: i64,
+}
+
+/// Emitted when a batch of records is re-pointed at the vault's current owner
+#[event]
+pub struct OwnerRecordsSynced {
+    /// Owner-independent vault id
+    pub vault_id: Pubkey,
+    /// Current vault owner written to the records
+    pub owner: Pubkey,
+    /// Number of accounts updated in this batch
+    pub records_updated: u32,
+    /// Sync timestamp
+    pub timestamp: i64,
+}
+
 // ============================================================================
 // MEMBERSHIP EVENTS
 // ============================================================================
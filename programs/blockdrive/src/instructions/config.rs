@@ -0,0 +1,391 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::program::Blockdrive;
+use crate::state::{VaultConfig, MAX_CLEANUP_TIP};
+use crate::errors::BlockDriveError;
+use crate::events::{
+    ConfigInitialized, FeesUpdated, LimitsUpdated, PauseStatusChanged,
+    FeeCollectorUpdated, FeeCollected, FeeType, CleanupTipUpdated, CleanupTipsFunded,
+};
+
+// =============================================================================
+// ACCOUNT CONTEXTS
+// =============================================================================
+
+/// Initialize the global config (one-time)
+/// Only the program's upgrade authority can create the config
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = VaultConfig::SIZE,
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Wallet that receives program fees (doesn't need to sign)
+    pub fee_collector: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ BlockDriveError::Unauthorized
+    )]
+    pub program: Program<'info, Blockdrive>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ BlockDriveError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Update config values (fees, limits, pause switch)
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ BlockDriveError::Unauthorized
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Change the fee collector wallet
+#[derive(Accounts)]
+pub struct UpdateFeeCollector<'info> {
+    #[account(
+        mut,
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ BlockDriveError::Unauthorized
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: New wallet that receives program fees (doesn't need to sign)
+    pub new_fee_collector: UncheckedAccount<'info>,
+}
+
+/// Top up the cleanup tip pool held by the config
+#[derive(Accounts)]
+pub struct FundCleanupTips<'info> {
+    #[account(
+        mut,
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// Any wallet, typically the fee collector
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// =============================================================================
+// INSTRUCTION HANDLERS
+// =============================================================================
+
+/// Initialize the global config with fees and limits
+pub fn initialize_config(
+    ctx: Context<InitializeConfig>,
+    file_registration_fee: u64,
+    delegation_fee: u64,
+    max_file_size: u64,
+    max_files_per_vault: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &mut ctx.accounts.config;
+
+    config.bump = ctx.bumps.config;
+    config.authority = ctx.accounts.authority.key();
+    config.fee_collector = ctx.accounts.fee_collector.key();
+    config.file_registration_fee = file_registration_fee;
+    config.delegation_fee = delegation_fee;
+    config.max_file_size = max_file_size;
+    config.max_files_per_vault = max_files_per_vault;
+    config.is_paused = false;
+    config.created_at = clock.unix_timestamp;
+    config.updated_at = clock.unix_timestamp;
+    config.cleanup_tip = 0;
+    config.reserved = [0u8; 56];
+
+    emit!(ConfigInitialized {
+        config: config.key(),
+        authority: config.authority,
+        fee_collector: config.fee_collector,
+        file_registration_fee,
+        delegation_fee,
+        max_file_size,
+        max_files_per_vault,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Config initialized: authority={}", config.authority);
+    Ok(())
+}
+
+/// Update program fees
+pub fn update_fees(
+    ctx: Context<UpdateConfig>,
+    file_registration_fee: u64,
+    delegation_fee: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &mut ctx.accounts.config;
+
+    config.file_registration_fee = file_registration_fee;
+    config.delegation_fee = delegation_fee;
+    config.updated_at = clock.unix_timestamp;
+
+    emit!(FeesUpdated {
+        config: config.key(),
+        file_registration_fee,
+        delegation_fee,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Update file size and files-per-vault limits
+pub fn update_limits(
+    ctx: Context<UpdateConfig>,
+    max_file_size: u64,
+    max_files_per_vault: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &mut ctx.accounts.config;
+
+    config.max_file_size = max_file_size;
+    config.max_files_per_vault = max_files_per_vault;
+    config.updated_at = clock.unix_timestamp;
+
+    emit!(LimitsUpdated {
+        config: config.key(),
+        max_file_size,
+        max_files_per_vault,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Pause/unpause program
+pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &mut ctx.accounts.config;
+
+    config.is_paused = paused;
+    config.updated_at = clock.unix_timestamp;
+
+    emit!(PauseStatusChanged {
+        config: config.key(),
+        is_paused: paused,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Program paused: {}", paused);
+    Ok(())
+}
+
+/// Set the tip paid to crankers that close expired delegations and sessions
+pub fn update_cleanup_tip(ctx: Context<UpdateConfig>, cleanup_tip: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &mut ctx.accounts.config;
+
+    require!(cleanup_tip <= MAX_CLEANUP_TIP, BlockDriveError::CleanupTipTooHigh);
+
+    config.cleanup_tip = cleanup_tip;
+    config.updated_at = clock.unix_timestamp;
+
+    emit!(CleanupTipUpdated {
+        config: config.key(),
+        cleanup_tip,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Change the fee collector wallet
+pub fn update_fee_collector(ctx: Context<UpdateFeeCollector>) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &mut ctx.accounts.config;
+
+    let old_fee_collector = config.fee_collector;
+    config.fee_collector = ctx.accounts.new_fee_collector.key();
+    config.updated_at = clock.unix_timestamp;
+
+    emit!(FeeCollectorUpdated {
+        config: config.key(),
+        old_fee_collector,
+        new_fee_collector: config.fee_collector,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Add lamports to the pool that cleanup tips are paid from
+pub fn fund_cleanup_tips(ctx: Context<FundCleanupTips>, amount: u64) -> Result<()> {
+    let clock = Clock::get()?;
+
+    require!(
+        ctx.accounts.funder.lamports() >= amount,
+        BlockDriveError::InsufficientFunds
+    );
+
+    let cpi_accounts = system_program::Transfer {
+        from: ctx.accounts.funder.to_account_info(),
+        to: ctx.accounts.config.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+    system_program::transfer(cpi_ctx, amount)?;
+
+    emit!(CleanupTipsFunded {
+        config: ctx.accounts.config.key(),
+        funder: ctx.accounts.funder.key(),
+        amount,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// =============================================================================
+// HELPER FUNCTIONS
+// =============================================================================
+
+/// Transfer a program fee from `payer` to the fee collector
+/// No-op when `amount` is 0. `fee_collector` must already be checked
+/// against `config.fee_collector` by the calling context.
+pub fn collect_fee<'info>(
+    payer: &Signer<'info>,
+    fee_collector: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+    fee_type: FeeType,
+    reference: Pubkey,
+    timestamp: i64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    require!(payer.lamports() >= amount, BlockDriveError::InsufficientFunds);
+
+    let cpi_accounts = system_program::Transfer {
+        from: payer.to_account_info(),
+        to: fee_collector.clone(),
+    };
+    let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+    system_program::transfer(cpi_ctx, amount)?;
+
+    emit!(FeeCollected {
+        payer: payer.key(),
+        fee_collector: fee_collector.key(),
+        reference,
+        fee_type,
+        amount,
+        timestamp,
+    });
+
+    Ok(())
+}
+
+/// Derive the VaultConfig PDA address
+pub fn derive_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VaultConfig::SEED_PREFIX], program_id)
+}
+
+/// Pay config.cleanup_tip to a cranker out of the config's tip pool
+/// The pool is whatever the config holds above its rent-exempt minimum; the
+/// tip shrinks to what is left when it runs low. Returns the tip actually paid
+pub fn pay_cleanup_tip<'info>(
+    config: &Account<'info, VaultConfig>,
+    cranker: &AccountInfo<'info>,
+) -> Result<u64> {
+    let pool = config.to_account_info();
+    let reserve = Rent::get()?.minimum_balance(pool.data_len());
+    transfer_tip(&pool, cranker, config.cleanup_tip, reserve)
+}
+
+/// Move up to `tip` lamports from `pool` to `cranker`, leaving `reserve` behind
+fn transfer_tip<'info>(
+    pool: &AccountInfo<'info>,
+    cranker: &AccountInfo<'info>,
+    tip: u64,
+    reserve: u64,
+) -> Result<u64> {
+    let pool_lamports = pool.lamports();
+    let tip = tip.min(pool_lamports.saturating_sub(reserve));
+    if tip == 0 {
+        return Ok(0);
+    }
+
+    **pool.try_borrow_mut_lamports()? = pool_lamports - tip;
+    let cranker_lamports = cranker.lamports();
+    **cranker.try_borrow_mut_lamports()? = cranker_lamports.saturating_add(tip);
+
+    Ok(tip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cleanup_tip_keeps_pool_reserve() {
+        let owner = crate::ID;
+        let pool_key = Pubkey::new_unique();
+        let cranker_key = Pubkey::new_unique();
+        let mut pool_lamports = 1_000u64;
+        let mut cranker_lamports = 5u64;
+        let mut pool_data = [0u8; 8];
+        let mut cranker_data = [0u8; 0];
+        let pool = AccountInfo::new(
+            &pool_key,
+            false,
+            true,
+            &mut pool_lamports,
+            &mut pool_data,
+            &owner,
+            false,
+            0,
+        );
+        let cranker = AccountInfo::new(
+            &cranker_key,
+            true,
+            true,
+            &mut cranker_lamports,
+            &mut cranker_data,
+            &owner,
+            false,
+            0,
+        );
+
+        // 400 lamports of the pool are its rent reserve
+        assert_eq!(transfer_tip(&pool, &cranker, 0, 400).unwrap(), 0);
+        assert_eq!(transfer_tip(&pool, &cranker, 300, 400).unwrap(), 300);
+        assert_eq!(pool.lamports(), 700);
+        assert_eq!(cranker.lamports(), 305);
+
+        // A drained pool pays what it has above the reserve, then nothing
+        assert_eq!(transfer_tip(&pool, &cranker, 5_000, 400).unwrap(), 300);
+        assert_eq!(pool.lamports(), 400);
+        assert_eq!(cranker.lamports(), 605);
+        assert_eq!(transfer_tip(&pool, &cranker, 300, 400).unwrap(), 0);
+        assert_eq!(pool.lamports(), 400);
+    }
+}
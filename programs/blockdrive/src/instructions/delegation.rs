@@ -1,7 +1,14 @@
 use anchor_lang::prelude::*;
-use crate::state::{FileRecord, Delegation, PermissionLevel};
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use crate::state::{FileRecord, Delegation, PermissionLevel, VaultConfig, MAX_RESHARE_DEPTH};
 use crate::errors::BlockDriveError;
-use crate::events::{DelegationCreated, DelegationRevoked, DelegationUpdated};
+use crate::events::{
+    DelegationCreated, DelegationRevoked, DelegationUpdated, DelegationAccepted,
+    DelegationRejected, DelegationReshared, ExpiredDelegationClosed, FeeType,
+};
+use crate::instructions::config::{collect_fee, pay_cleanup_tip};
+use crate::instructions::vault::{require_vault_not_frozen, require_vault_writable};
 
 #[derive(Accounts)]
 pub struct CreateDelegation<'info> {
@@ -16,20 +23,33 @@ pub struct CreateDelegation<'info> {
 
     #[account(
         mut,
-        has_one = owner @ BlockDriveError::Unauthorized
+        constraint = file_record.can_delegate(&grantor.key()) @ BlockDriveError::Unauthorized
     )]
     pub file_record: Account<'info, FileRecord>,
 
-    /// The file owner granting access
+    /// CHECK: The file's UserVault or UserVaultMaster; owner and frozen status checked in handler
+    #[account(address = file_record.vault @ BlockDriveError::Unauthorized)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The file owner granting access; must match file_record.owner
     #[account(mut)]
     pub grantor: Signer<'info>,
 
     /// CHECK: The wallet receiving delegation (doesn't need to sign)
     pub grantee: UncheckedAccount<'info>,
 
-    /// The owner must match file_record.owner
-    /// CHECK: Verified via has_one constraint
-    pub owner: UncheckedAccount<'info>,
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// CHECK: Receives the delegation fee; verified against config.fee_collector
+    #[account(
+        mut,
+        address = config.fee_collector @ BlockDriveError::Unauthorized
+    )]
+    pub fee_collector: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
@@ -51,6 +71,23 @@ pub struct RevokeDelegation<'info> {
     pub grantor: Signer<'info>,
 }
 
+/// Revoke delegations of a file as its owner, whoever granted them
+/// (delegation, grantor) pairs are passed as remaining accounts
+#[derive(Accounts)]
+pub struct RevokeFileDelegations<'info> {
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    /// CHECK: The file's UserVault or UserVaultMaster; owner and frozen status checked in handler
+    #[account(address = file_record.vault @ BlockDriveError::Unauthorized)]
+    pub vault: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateDelegation<'info> {
     #[account(
@@ -59,9 +96,158 @@ pub struct UpdateDelegation<'info> {
     )]
     pub delegation: Account<'info, Delegation>,
 
+    /// The grantor's own delegation; required when updating a sub-delegation
+    #[account(
+        seeds = [Delegation::SEED_PREFIX, delegation.file_record.as_ref(), grantor.key().as_ref()],
+        bump = parent_delegation.bump
+    )]
+    pub parent_delegation: Option<Account<'info, Delegation>>,
+
     pub grantor: Signer<'info>,
 }
 
+/// Close an expired delegation (anyone can call)
+#[derive(Accounts)]
+pub struct CloseExpiredDelegation<'info> {
+    #[account(
+        mut,
+        close = grantor,
+        has_one = grantor @ BlockDriveError::Unauthorized,
+        has_one = file_record
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// The delegation's FileRecord; deleting or archiving a file revokes its
+    /// delegations first, so it is still open
+    #[account(
+        mut,
+        seeds = [FileRecord::SEED_PREFIX, file_record.vault.as_ref(), &file_record.file_id],
+        bump = file_record.bump
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    /// CHECK: Paid the delegation's rent and receives it back; verified via has_one
+    #[account(mut)]
+    pub grantor: UncheckedAccount<'info>,
+
+    /// Pays the cleanup tip out of its balance above rent
+    #[account(
+        mut,
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// Any wallet; receives the cleanup tip
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+}
+
+/// Re-share a file: a Reshare grantee creates a child delegation
+/// Ancestors of the parent delegation (nearest first) are passed as remaining accounts
+#[derive(Accounts)]
+pub struct ReshareDelegation<'info> {
+    #[account(
+        init,
+        payer = grantor,
+        space = Delegation::SIZE,
+        seeds = [Delegation::SEED_PREFIX, file_record.key().as_ref(), grantee.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// The re-sharing grantee's own delegation
+    #[account(
+        seeds = [Delegation::SEED_PREFIX, file_record.key().as_ref(), grantor.key().as_ref()],
+        bump = parent_delegation.bump,
+        has_one = file_record
+    )]
+    pub parent_delegation: Account<'info, Delegation>,
+
+    #[account(mut)]
+    pub file_record: Account<'info, FileRecord>,
+
+    /// CHECK: The file's UserVault or UserVaultMaster; frozen status checked in handler
+    #[account(address = file_record.vault @ BlockDriveError::Unauthorized)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// The Reshare grantee creating the sub-delegation
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+
+    /// CHECK: The wallet receiving delegation (doesn't need to sign)
+    pub grantee: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// CHECK: Receives the delegation fee; verified against config.fee_collector
+    #[account(
+        mut,
+        address = config.fee_collector @ BlockDriveError::Unauthorized
+    )]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accept a delegation
+/// Ancestors of a sub-delegation (nearest first) are passed as remaining accounts
+#[derive(Accounts)]
+pub struct AcceptDelegation<'info> {
+    #[account(
+        mut,
+        has_one = grantee @ BlockDriveError::Unauthorized
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    pub grantee: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RejectDelegation<'info> {
+    #[account(
+        mut,
+        close = grantor,
+        has_one = grantee @ BlockDriveError::Unauthorized,
+        has_one = grantor @ BlockDriveError::Unauthorized,
+        has_one = file_record
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    #[account(mut)]
+    pub file_record: Account<'info, FileRecord>,
+
+    /// CHECK: Receives the rent refund; verified via has_one constraint
+    #[account(mut)]
+    pub grantor: UncheckedAccount<'info>,
+
+    pub grantee: Signer<'info>,
+}
+
+/// Grow a delegation created before re-sharing added parent_delegation
+/// Such delegations are too small to deserialize, so the account is taken
+/// unchecked. Anyone may pay for the resize
+#[derive(Accounts)]
+pub struct ResizeDelegation<'info> {
+    /// CHECK: Program ownership is enforced here; the discriminator is read from
+    /// the raw data in the handler
+    #[account(
+        mut,
+        owner = crate::ID @ BlockDriveError::Unauthorized
+    )]
+    pub delegation: UncheckedAccount<'info>,
+
+    /// Pays the rent for the added byte
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 /// Create a new file delegation
 pub fn create_delegation(
     ctx: Context<CreateDelegation>,
@@ -73,8 +259,15 @@ pub fn create_delegation(
     let file_record = &mut ctx.accounts.file_record;
     let clock = Clock::get()?;
 
+    require!(!ctx.accounts.config.is_paused, BlockDriveError::ProgramPaused);
+    require_vault_writable(&ctx.accounts.vault, &file_record.owner)?;
+
     // Validate file is active
     require!(file_record.is_active(), BlockDriveError::FileNotActive);
+    require!(
+        file_record.can_add_delegation(),
+        BlockDriveError::DelegationLimitReached
+    );
 
     // Cannot delegate to self
     require!(
@@ -107,11 +300,22 @@ pub fn create_delegation(
     delegation.is_accepted = false;
     delegation.access_count = 0;
     delegation.last_accessed_at = 0;
-    delegation.reserved = [0u8; 32];
+    delegation.depth = 0;
+    delegation.parent_delegation = Pubkey::default();
 
     // Update file record
     file_record.add_delegation();
 
+    collect_fee(
+        &ctx.accounts.grantor,
+        &ctx.accounts.fee_collector.to_account_info(),
+        &ctx.accounts.system_program,
+        ctx.accounts.config.delegation_fee,
+        FeeType::Delegation,
+        delegation.key(),
+        clock.unix_timestamp,
+    )?;
+
     emit!(DelegationCreated {
         file_record: file_record.key(),
         grantor: ctx.accounts.grantor.key(),
@@ -144,6 +348,23 @@ pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
     Ok(())
 }
 
+/// Revoke delegations of a file as its owner
+/// Unlike revoke_delegation this also reaches sub-delegations, so a file with
+/// many delegations can be cleared in batches before it is deleted or archived
+pub fn revoke_file_delegations<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RevokeFileDelegations<'info>>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
+    require_vault_writable(&ctx.accounts.vault, &ctx.accounts.file_record.owner)?;
+
+    revoke_delegation_batch(
+        &mut ctx.accounts.file_record,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+    )
+}
+
 /// Update delegation permissions
 pub fn update_delegation(
     ctx: Context<UpdateDelegation>,
@@ -167,6 +388,19 @@ pub fn update_delegation(
         );
     }
 
+    // Sub-delegations stay within the grantor's own delegation
+    if delegation.is_reshared() {
+        let parent_delegation = ctx
+            .accounts
+            .parent_delegation
+            .as_ref()
+            .ok_or(BlockDriveError::DelegationChainBroken)?;
+        require!(
+            parent_delegation.covers(perm_level, expires_at),
+            BlockDriveError::ReshareExceedsParent
+        );
+    }
+
     delegation.permission_level = perm_level;
     delegation.expires_at = expires_at;
 
@@ -180,3 +414,382 @@ pub fn update_delegation(
 
     Ok(())
 }
+
+/// Accept a delegation (grantee signs)
+pub fn accept_delegation(ctx: Context<AcceptDelegation>) -> Result<()> {
+    let delegation = &mut ctx.accounts.delegation;
+    let clock = Clock::get()?;
+
+    require!(
+        !delegation.is_expired(clock.unix_timestamp),
+        BlockDriveError::DelegationExpired
+    );
+    require!(delegation.is_active, BlockDriveError::DelegationNotActive);
+    require!(!delegation.is_accepted, BlockDriveError::DelegationAlreadyAccepted);
+
+    // A sub-delegation cannot be claimed once any ancestor is revoked
+    if delegation.is_reshared() {
+        verify_delegation_chain(
+            delegation,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+            ctx.program_id,
+        )?;
+    }
+
+    delegation.is_accepted = true;
+
+    emit!(DelegationAccepted {
+        file_record: delegation.file_record,
+        grantor: delegation.grantor,
+        grantee: delegation.grantee,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Reject a delegation (grantee signs)
+/// Closes the delegation and refunds its rent to the grantor
+pub fn reject_delegation(ctx: Context<RejectDelegation>) -> Result<()> {
+    let delegation = &ctx.accounts.delegation;
+    let file_record = &mut ctx.accounts.file_record;
+    let clock = Clock::get()?;
+
+    // Update file record
+    file_record.remove_delegation();
+
+    emit!(DelegationRejected {
+        file_record: file_record.key(),
+        grantor: delegation.grantor,
+        grantee: delegation.grantee,
+        timestamp: clock.unix_timestamp,
+    });
+
+    // Account is closed automatically via `close = grantor` constraint
+    Ok(())
+}
+
+/// Re-share a file as a Reshare grantee
+/// The child is capped at the parent's permission level and expiry and records
+/// its depth; it stays usable only while every ancestor remains valid
+pub fn reshare_delegation(
+    ctx: Context<ReshareDelegation>,
+    encrypted_file_key: [u8; 128],
+    permission_level: u8,
+    expires_at: i64,
+) -> Result<()> {
+    let parent_delegation = &ctx.accounts.parent_delegation;
+    let file_record = &mut ctx.accounts.file_record;
+    let clock = Clock::get()?;
+
+    require!(!ctx.accounts.config.is_paused, BlockDriveError::ProgramPaused);
+    require_vault_not_frozen(&ctx.accounts.vault)?;
+    require!(file_record.is_active(), BlockDriveError::FileNotActive);
+    require!(
+        file_record.can_add_delegation(),
+        BlockDriveError::DelegationLimitReached
+    );
+
+    let grantor = ctx.accounts.grantor.key();
+    let grantee = ctx.accounts.grantee.key();
+    require!(
+        grantee != grantor && grantee != file_record.owner,
+        BlockDriveError::CannotDelegateToSelf
+    );
+
+    // The parent must itself be live, allowed to reshare and rooted in the owner
+    require!(
+        parent_delegation.is_valid(clock.unix_timestamp),
+        BlockDriveError::DelegationNotActive
+    );
+    require!(parent_delegation.can_reshare(), BlockDriveError::InsufficientPermissions);
+    verify_delegation_chain(
+        parent_delegation,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+        ctx.program_id,
+    )?;
+
+    let depth = parent_delegation.depth.saturating_add(1);
+    require!(depth <= MAX_RESHARE_DEPTH, BlockDriveError::ReshareDepthExceeded);
+
+    let perm_level = PermissionLevel::from_u8(permission_level)
+        .ok_or(BlockDriveError::InvalidPermissionLevel)?;
+    if expires_at > 0 {
+        require!(
+            expires_at > clock.unix_timestamp,
+            BlockDriveError::InvalidExpiration
+        );
+    }
+    require!(
+        parent_delegation.covers(perm_level, expires_at),
+        BlockDriveError::ReshareExceedsParent
+    );
+
+    let delegation = &mut ctx.accounts.delegation;
+    delegation.bump = ctx.bumps.delegation;
+    delegation.file_record = file_record.key();
+    delegation.grantor = grantor;
+    delegation.grantee = grantee;
+    delegation.encrypted_file_key = encrypted_file_key;
+    delegation.permission_level = perm_level;
+    delegation.expires_at = expires_at;
+    delegation.created_at = clock.unix_timestamp;
+    delegation.is_active = true;
+    delegation.is_accepted = false;
+    delegation.access_count = 0;
+    delegation.last_accessed_at = 0;
+    delegation.depth = depth;
+    delegation.parent_delegation = parent_delegation.key();
+
+    file_record.add_delegation();
+
+    collect_fee(
+        &ctx.accounts.grantor,
+        &ctx.accounts.fee_collector.to_account_info(),
+        &ctx.accounts.system_program,
+        ctx.accounts.config.delegation_fee,
+        FeeType::Delegation,
+        delegation.key(),
+        clock.unix_timestamp,
+    )?;
+
+    emit!(DelegationReshared {
+        file_record: file_record.key(),
+        parent_delegation: parent_delegation.key(),
+        grantor,
+        grantee,
+        permission_level,
+        expires_at,
+        depth,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Check that every ancestor of a sub-delegation is still valid
+/// `ancestors` holds the parent, grandparent, ... (nearest first), one per level
+/// of depth, each at the parent_delegation recorded on the one before it. A
+/// revoked ancestor no longer exists, and a re-created one is newer than its
+/// child, so either breaks the chain and the whole subtree with it
+pub fn verify_delegation_chain(
+    delegation: &Delegation,
+    ancestors: &[AccountInfo],
+    timestamp: i64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    require!(
+        ancestors.len() >= delegation.depth as usize,
+        BlockDriveError::DelegationChainBroken
+    );
+
+    let file_record = delegation.file_record;
+    let mut child_parent = delegation.parent_delegation;
+    let mut child_created_at = delegation.created_at;
+    let mut child_depth = delegation.depth;
+
+    for info in ancestors.iter().take(delegation.depth as usize) {
+        require_keys_eq!(*info.owner, *program_id, BlockDriveError::DelegationChainBroken);
+        require_keys_eq!(info.key(), child_parent, BlockDriveError::DelegationChainBroken);
+        let parent = Delegation::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+        require!(
+            parent.file_record == file_record
+                && parent.depth.saturating_add(1) == child_depth
+                && parent.created_at <= child_created_at,
+            BlockDriveError::DelegationChainBroken
+        );
+        require!(parent.is_valid(timestamp), BlockDriveError::DelegationChainBroken);
+        require!(parent.can_reshare(), BlockDriveError::DelegationChainBroken);
+
+        child_parent = parent.parent_delegation;
+        child_created_at = parent.created_at;
+        child_depth = parent.depth;
+    }
+
+    Ok(())
+}
+
+/// Close the remaining delegations of a file that is being deleted or archived
+/// `accounts` holds (delegation, grantor) pairs; each grantor gets its rent back.
+/// Fails unless the file is left with no delegations
+pub fn revoke_all_delegations<'info>(
+    file_record: &mut Account<'info, FileRecord>,
+    accounts: &'info [AccountInfo<'info>],
+    timestamp: i64,
+) -> Result<()> {
+    revoke_delegation_batch(file_record, accounts, timestamp)?;
+
+    require!(
+        file_record.delegation_count == 0,
+        BlockDriveError::FileHasDelegations
+    );
+
+    Ok(())
+}
+
+/// Close a batch of a file's delegations
+/// `accounts` holds (delegation, grantor) pairs; each grantor gets its rent back
+fn revoke_delegation_batch<'info>(
+    file_record: &mut Account<'info, FileRecord>,
+    accounts: &'info [AccountInfo<'info>],
+    timestamp: i64,
+) -> Result<()> {
+    require!(
+        accounts.len().is_multiple_of(2),
+        BlockDriveError::InvalidDelegationAccounts
+    );
+
+    for pair in accounts.chunks_exact(2) {
+        let delegation = Account::<Delegation>::try_from(&pair[0])?;
+        let grantor = &pair[1];
+        require!(
+            delegation.file_record == file_record.key() && grantor.key() == delegation.grantor,
+            BlockDriveError::InvalidDelegationAccounts
+        );
+
+        file_record.remove_delegation();
+
+        emit!(DelegationRevoked {
+            file_record: file_record.key(),
+            grantor: delegation.grantor,
+            grantee: delegation.grantee,
+            timestamp,
+        });
+
+        delegation.close(grantor.clone())?;
+    }
+
+    Ok(())
+}
+
+/// Close an expired delegation and return its rent to the grantor
+/// Permissionless; the cranker receives config.cleanup_tip from the tip pool
+pub fn close_expired_delegation(ctx: Context<CloseExpiredDelegation>) -> Result<()> {
+    let delegation = &ctx.accounts.delegation;
+    let clock = Clock::get()?;
+
+    require!(
+        delegation.is_expired(clock.unix_timestamp),
+        BlockDriveError::DelegationNotExpired
+    );
+
+    ctx.accounts.file_record.remove_delegation();
+
+    let tip = pay_cleanup_tip(
+        &ctx.accounts.config,
+        &ctx.accounts.cranker.to_account_info(),
+    )?;
+
+    emit!(ExpiredDelegationClosed {
+        delegation: delegation.key(),
+        file_record: delegation.file_record,
+        grantor: delegation.grantor,
+        cranker: ctx.accounts.cranker.key(),
+        tip,
+        timestamp: clock.unix_timestamp,
+    });
+
+    // Account is closed automatically via `close = grantor` constraint
+    Ok(())
+}
+
+/// Grow a pre-reshare delegation to the current layout
+/// The payer tops up rent; the appended byte completes a zeroed
+/// parent_delegation, which with depth 0 is what an owner grant records.
+/// No-op if already resized
+pub fn resize_delegation(ctx: Context<ResizeDelegation>) -> Result<()> {
+    let delegation = ctx.accounts.delegation.to_account_info();
+
+    {
+        let data = delegation.try_borrow_data()?;
+        require!(
+            data.len() >= Delegation::LEGACY_SIZE && data[..8] == Delegation::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        if data.len() >= Delegation::SIZE {
+            msg!("Delegation already at current size");
+            return Ok(());
+        }
+    }
+
+    let required = Rent::get()?.minimum_balance(Delegation::SIZE);
+    let shortfall = required.saturating_sub(delegation.lamports());
+    if shortfall > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.payer.to_account_info(),
+            to: delegation.clone(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, shortfall)?;
+    }
+
+    delegation.realloc(Delegation::SIZE, true)?;
+
+    msg!("Delegation resized to {} bytes", Delegation::SIZE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_follows_recorded_parent() {
+        let program_id = crate::ID;
+        let file_record = Pubkey::new_unique();
+        let parent_key = Pubkey::new_unique();
+        let other_key = Pubkey::new_unique();
+
+        let parent = Delegation {
+            file_record,
+            permission_level: PermissionLevel::Reshare,
+            created_at: 10,
+            is_active: true,
+            ..Default::default()
+        };
+        let child = Delegation {
+            file_record,
+            created_at: 20,
+            is_active: true,
+            depth: 1,
+            parent_delegation: parent_key,
+            ..Default::default()
+        };
+
+        let mut data = Vec::with_capacity(Delegation::SIZE);
+        parent.try_serialize(&mut data).unwrap();
+        let mut parent_lamports = 0u64;
+        let mut other_lamports = 0u64;
+        let mut other_data = data.clone();
+        let parent_info = AccountInfo::new(
+            &parent_key,
+            false,
+            false,
+            &mut parent_lamports,
+            &mut data,
+            &program_id,
+            false,
+            0,
+        );
+        // Same contents at another address, e.g. the grantor's delegation on
+        // a different chain
+        let other_info = AccountInfo::new(
+            &other_key,
+            false,
+            false,
+            &mut other_lamports,
+            &mut other_data,
+            &program_id,
+            false,
+            0,
+        );
+
+        assert!(verify_delegation_chain(&child, &[parent_info], 30, &program_id).is_ok());
+        assert!(verify_delegation_chain(&child, &[other_info], 30, &program_id).is_err());
+        assert!(verify_delegation_chain(&child, &[], 30, &program_id).is_err());
+    }
+}
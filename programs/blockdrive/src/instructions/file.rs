@@ -1,7 +1,20 @@
 use anchor_lang::prelude::*;
-use crate::state::{UserVault, FileRecord, FileStatus, SecurityLevel};
+use crate::state::{
+    UserVault, FileRecord, FileStatus, FileRegistration, VaultConfig, SessionDelegation, OperationFlags,
+    Delegation,
+};
 use crate::errors::BlockDriveError;
-use crate::events::{FileRegistered, FileStorageUpdated, FileArchived, FileDeleted, FileAccessed};
+use crate::events::{
+    FileRegistered, FileStorageUpdated, FileArchived, FileUnarchived, FileDeleted, FileAccessed,
+    FileReencrypted, FileMetadataUpdated, FeeType,
+};
+use crate::instructions::config::collect_fee;
+use crate::instructions::vault::require_vault_writable;
+use crate::instructions::delegation::{verify_delegation_chain, revoke_all_delegations};
+
+/// Maximum FileRecords checked per find_stale_files call
+/// Keeps the returned Vec<Pubkey> within the 1024-byte return data limit
+pub const MAX_STALE_FILE_BATCH: usize = 31;
 
 #[derive(Accounts)]
 #[instruction(file_id: [u8; 16])]
@@ -17,12 +30,25 @@ pub struct RegisterFile<'info> {
 
     #[account(
         mut,
-        seeds = [UserVault::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
         bump = vault.bump,
         has_one = owner @ BlockDriveError::Unauthorized
     )]
     pub vault: Account<'info, UserVault>,
 
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// CHECK: Receives the registration fee; verified against config.fee_collector
+    #[account(
+        mut,
+        address = config.fee_collector @ BlockDriveError::Unauthorized
+    )]
+    pub fee_collector: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub owner: Signer<'info>,
 
@@ -38,7 +64,7 @@ pub struct UpdateFileStorage<'info> {
     pub file_record: Account<'info, FileRecord>,
 
     #[account(
-        seeds = [UserVault::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
         bump = vault.bump,
         has_one = owner @ BlockDriveError::Unauthorized
     )]
@@ -47,6 +73,33 @@ pub struct UpdateFileStorage<'info> {
     pub owner: Signer<'info>,
 }
 
+/// Update a file's filename/MIME hashes (legacy or sharded file)
+/// Signed by the owner, or by a relayer holding a session with UPDATE
+#[derive(Accounts)]
+pub struct UpdateFileMetadata<'info> {
+    #[account(mut)]
+    pub file_record: Account<'info, FileRecord>,
+
+    /// CHECK: The file's UserVault or UserVaultMaster; owner and frozen status checked in handler
+    #[account(address = file_record.vault @ BlockDriveError::Unauthorized)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// Required when the authority is not the file owner
+    #[account(
+        mut,
+        seeds = [
+            SessionDelegation::SEED_PREFIX,
+            file_record.owner.as_ref(),
+            authority.key().as_ref()
+        ],
+        bump = session.bump
+    )]
+    pub session: Option<Account<'info, SessionDelegation>>,
+
+    /// File owner or session relayer
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ArchiveFile<'info> {
     #[account(
@@ -56,7 +109,25 @@ pub struct ArchiveFile<'info> {
     pub file_record: Account<'info, FileRecord>,
 
     #[account(
-        seeds = [UserVault::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnarchiveFile<'info> {
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
         bump = vault.bump,
         has_one = owner @ BlockDriveError::Unauthorized
     )]
@@ -76,7 +147,7 @@ pub struct DeleteFile<'info> {
 
     #[account(
         mut,
-        seeds = [UserVault::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
         bump = vault.bump,
         has_one = owner @ BlockDriveError::Unauthorized
     )]
@@ -86,11 +157,43 @@ pub struct DeleteFile<'info> {
     pub owner: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ReencryptFile<'info> {
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        has_one = vault @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FindStaleFiles<'info> {
+    pub vault: Account<'info, UserVault>,
+}
+
 #[derive(Accounts)]
 pub struct RecordAccess<'info> {
     #[account(mut)]
     pub file_record: Account<'info, FileRecord>,
 
+    /// The accessor's delegation; required when the accessor is not the owner
+    #[account(
+        mut,
+        seeds = [Delegation::SEED_PREFIX, file_record.key().as_ref(), accessor.key().as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Option<Account<'info, Delegation>>,
+
     /// Can be owner or delegatee
     pub accessor: Signer<'info>,
 }
@@ -99,58 +202,53 @@ pub struct RecordAccess<'info> {
 pub fn register_file(
     ctx: Context<RegisterFile>,
     file_id: [u8; 16],
-    filename_hash: [u8; 32],
-    file_size: u64,
-    encrypted_size: u64,
-    mime_type_hash: [u8; 32],
-    security_level: u8,
-    encryption_commitment: [u8; 32],
-    critical_bytes_commitment: [u8; 32],
-    primary_cid: [u8; 64],
+    registration: FileRegistration,
 ) -> Result<()> {
+    let config = &ctx.accounts.config;
     let vault = &mut ctx.accounts.vault;
     let file_record = &mut ctx.accounts.file_record;
     let clock = Clock::get()?;
 
+    require!(!config.is_paused, BlockDriveError::ProgramPaused);
+
     // Validate vault is active
     require!(vault.is_active(), BlockDriveError::VaultNotActive);
 
-    // Validate security level
-    let sec_level = SecurityLevel::from_u8(security_level)
-        .ok_or(BlockDriveError::InvalidSecurityLevel)?;
+    // Enforce global limits
+    require!(config.is_file_size_allowed(registration.file_size), BlockDriveError::FileSizeExceeded);
+    require!(config.can_add_file(vault.file_count), BlockDriveError::VaultFileLimitReached);
 
     // Initialize file record
-    file_record.bump = ctx.bumps.file_record;
-    file_record.vault = vault.key();
-    file_record.owner = ctx.accounts.owner.key();
-    file_record.file_id = file_id;
-    file_record.filename_hash = filename_hash;
-    file_record.file_size = file_size;
-    file_record.encrypted_size = encrypted_size;
-    file_record.mime_type_hash = mime_type_hash;
-    file_record.security_level = sec_level;
-    file_record.encryption_commitment = encryption_commitment;
-    file_record.critical_bytes_commitment = critical_bytes_commitment;
-    file_record.primary_cid = primary_cid;
-    file_record.redundancy_cid = [0u8; 64];
-    file_record.provider_count = 1;
-    file_record.created_at = clock.unix_timestamp;
-    file_record.accessed_at = clock.unix_timestamp;
-    file_record.status = FileStatus::Active;
-    file_record.is_shared = false;
-    file_record.delegation_count = 0;
-    file_record.reserved = [0u8; 32];
+    file_record.set_inner(FileRecord::new(
+        ctx.bumps.file_record,
+        vault.key(),
+        ctx.accounts.owner.key(),
+        file_id,
+        &registration,
+        vault.key_epoch,
+        clock.unix_timestamp,
+    )?);
 
     // Update vault stats
-    vault.add_file(file_size, clock.unix_timestamp);
+    vault.add_file(registration.file_size, clock.unix_timestamp);
+
+    collect_fee(
+        &ctx.accounts.owner,
+        &ctx.accounts.fee_collector.to_account_info(),
+        &ctx.accounts.system_program,
+        config.file_registration_fee,
+        FeeType::FileRegistration,
+        file_record.key(),
+        clock.unix_timestamp,
+    )?;
 
     emit!(FileRegistered {
         vault: vault.key(),
         file_id,
         file_record: file_record.key(),
-        file_size,
-        encrypted_size,
-        security_level,
+        file_size: registration.file_size,
+        encrypted_size: registration.encrypted_size,
+        security_level: registration.security_level,
         timestamp: clock.unix_timestamp,
     });
 
@@ -180,13 +278,55 @@ pub fn update_file_storage(
     Ok(())
 }
 
+/// Update a file's filename and MIME type hashes
+/// `expected_nonce` is only checked (and consumed) when acting through a session
+pub fn update_file_metadata(
+    ctx: Context<UpdateFileMetadata>,
+    filename_hash: [u8; 32],
+    mime_type_hash: [u8; 32],
+    expected_nonce: u64,
+) -> Result<()> {
+    let file_record = &mut ctx.accounts.file_record;
+    let authority = ctx.accounts.authority.key();
+    let clock = Clock::get()?;
+
+    require!(file_record.is_active(), BlockDriveError::FileNotActive);
+    require_vault_writable(&ctx.accounts.vault, &file_record.owner)?;
+
+    if authority != file_record.owner {
+        let session = ctx
+            .accounts
+            .session
+            .as_mut()
+            .ok_or(BlockDriveError::InsufficientPermissions)?;
+        session.use_operation(OperationFlags::UPDATE, expected_nonce, clock.unix_timestamp)?;
+    }
+
+    file_record.filename_hash = filename_hash;
+    file_record.mime_type_hash = mime_type_hash;
+
+    emit!(FileMetadataUpdated {
+        vault: file_record.vault,
+        file_record: file_record.key(),
+        filename_hash,
+        mime_type_hash,
+        updated_by: authority,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
 /// Archive a file
-pub fn archive_file(ctx: Context<ArchiveFile>) -> Result<()> {
+/// Delegations passed as remaining accounts are revoked first
+pub fn archive_file<'info>(ctx: Context<'_, '_, 'info, 'info, ArchiveFile<'info>>) -> Result<()> {
     let file_record = &mut ctx.accounts.file_record;
     let clock = Clock::get()?;
 
     require!(file_record.is_active(), BlockDriveError::FileNotActive);
 
+    revoke_all_delegations(file_record, ctx.remaining_accounts, clock.unix_timestamp)?;
+
     file_record.status = FileStatus::Archived;
 
     emit!(FileArchived {
@@ -198,11 +338,43 @@ pub fn archive_file(ctx: Context<ArchiveFile>) -> Result<()> {
     Ok(())
 }
 
+/// Restore an archived file to active
+pub fn unarchive_file(ctx: Context<UnarchiveFile>) -> Result<()> {
+    let file_record = &mut ctx.accounts.file_record;
+    let clock = Clock::get()?;
+
+    require!(file_record.is_archived(), BlockDriveError::FileNotArchived);
+
+    file_record.status = FileStatus::Active;
+
+    emit!(FileUnarchived {
+        vault: ctx.accounts.vault.key(),
+        file_record: file_record.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
 /// Delete a file record
-pub fn delete_file(ctx: Context<DeleteFile>) -> Result<()> {
+/// Delegations passed as remaining accounts are revoked first
+pub fn delete_file<'info>(ctx: Context<'_, '_, 'info, 'info, DeleteFile<'info>>) -> Result<()> {
+    let clock = Clock::get()?;
+
+    revoke_all_delegations(
+        &mut ctx.accounts.file_record,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+    )?;
+
     let vault = &mut ctx.accounts.vault;
     let file_record = &ctx.accounts.file_record;
-    let clock = Clock::get()?;
+
+    // Versions are seeded off the record and would be orphaned
+    require!(
+        file_record.retained_versions == 0,
+        BlockDriveError::FileHasVersions
+    );
 
     // Update vault stats before closing
     vault.remove_file(file_record.file_size, clock.unix_timestamp);
@@ -218,6 +390,82 @@ pub fn delete_file(ctx: Context<DeleteFile>) -> Result<()> {
     Ok(())
 }
 
+/// Re-encrypt a file under the vault's current master key
+/// Replaces the commitments and CIDs and moves the file to the current epoch
+pub fn reencrypt_file(
+    ctx: Context<ReencryptFile>,
+    encryption_commitment: [u8; 32],
+    critical_bytes_commitment: [u8; 32],
+    primary_cid: [u8; 64],
+    redundancy_cid: [u8; 64],
+) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+    let file_record = &mut ctx.accounts.file_record;
+    let clock = Clock::get()?;
+
+    require!(vault.is_active(), BlockDriveError::VaultNotActive);
+    require!(
+        file_record.is_active() || file_record.is_archived(),
+        BlockDriveError::FileNotActive
+    );
+    require!(
+        file_record.needs_reencryption(vault.key_epoch),
+        BlockDriveError::FileKeyEpochCurrent
+    );
+
+    let previous_key_epoch = file_record.key_epoch;
+
+    file_record.encryption_commitment = encryption_commitment;
+    file_record.critical_bytes_commitment = critical_bytes_commitment;
+    file_record.primary_cid = primary_cid;
+    file_record.redundancy_cid = redundancy_cid;
+    file_record.key_epoch = vault.key_epoch;
+
+    emit!(FileReencrypted {
+        vault: vault.key(),
+        file_record: file_record.key(),
+        previous_key_epoch,
+        key_epoch: file_record.key_epoch,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Report which of the given FileRecords lag behind the vault's key epoch
+/// FileRecords are passed as remaining accounts; records of other vaults are ignored
+pub fn find_stale_files<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FindStaleFiles<'info>>,
+) -> Result<Vec<Pubkey>> {
+    let vault = &ctx.accounts.vault;
+    collect_stale_files(vault.key(), vault.key_epoch, ctx.remaining_accounts)
+}
+
+/// Filter `accounts` down to the FileRecords of `vault` behind `key_epoch`
+/// Shared by the legacy and sharded queries
+pub(crate) fn collect_stale_files<'info>(
+    vault: Pubkey,
+    key_epoch: u32,
+    accounts: &'info [AccountInfo<'info>],
+) -> Result<Vec<Pubkey>> {
+    require!(
+        accounts.len() <= MAX_STALE_FILE_BATCH,
+        BlockDriveError::BatchTooLarge
+    );
+
+    let mut stale = Vec::new();
+    for account in accounts.iter() {
+        let file_record = Account::<FileRecord>::try_from(account)?;
+        if file_record.vault == vault && file_record.needs_reencryption(key_epoch) {
+            stale.push(file_record.key());
+        }
+    }
+
+    msg!("{} of {} files behind key epoch {}", stale.len(), accounts.len(), key_epoch);
+
+    Ok(stale)
+}
+
 /// Record file access
 pub fn record_access(ctx: Context<RecordAccess>) -> Result<()> {
     let file_record = &mut ctx.accounts.file_record;
@@ -225,6 +473,31 @@ pub fn record_access(ctx: Context<RecordAccess>) -> Result<()> {
 
     require!(file_record.is_active(), BlockDriveError::FileNotActive);
 
+    if ctx.accounts.accessor.key() != file_record.owner {
+        let delegation = ctx
+            .accounts
+            .delegation
+            .as_mut()
+            .ok_or(BlockDriveError::InsufficientPermissions)?;
+
+        require!(
+            !delegation.is_expired(clock.unix_timestamp),
+            BlockDriveError::DelegationExpired
+        );
+        require!(delegation.is_active, BlockDriveError::DelegationNotActive);
+        require!(delegation.can_download(), BlockDriveError::InsufficientPermissions);
+
+        // Sub-delegations die with any revoked ancestor
+        verify_delegation_chain(
+            delegation,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+            ctx.program_id,
+        )?;
+
+        delegation.record_access(clock.unix_timestamp);
+    }
+
     file_record.record_access(clock.unix_timestamp);
 
     emit!(FileAccessed {
@@ -0,0 +1,450 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    UserVault, UserVaultMaster, UserVaultShard, UserVaultIndex, FileRecord, VaultConfig,
+    Delegation, FileVersion,
+};
+use crate::errors::BlockDriveError;
+use crate::events::{VaultMasterCreated, LegacyFileMigrated, LegacyVaultMigrated};
+use crate::instructions::sharding::{allocate_index_page, create_pda_account};
+
+// =============================================================================
+// ACCOUNT CONTEXTS
+// =============================================================================
+
+/// Start migrating a legacy UserVault to sharded storage
+/// Creates the Vault Master and allocates the Vault Index if they don't exist yet
+#[derive(Accounts)]
+pub struct MigrateLegacyVault<'info> {
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = UserVaultMaster::SIZE,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// CHECK: Allocated in the handler if missing; extend_vault_index grows and
+    /// initializes it
+    #[account(
+        mut,
+        seeds = [UserVaultIndex::SEED_PREFIX, vault_master.key().as_ref()],
+        bump
+    )]
+    pub vault_index: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Move a single legacy FileRecord into a shard slot and index entry
+/// The legacy record is closed and re-created under the Vault Master seeds,
+/// along with its delegations and versions passed as remaining accounts
+#[derive(Accounts)]
+pub struct MigrateLegacyFile<'info> {
+    #[account(
+        mut,
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [FileRecord::SEED_PREFIX, vault.key().as_ref(), &legacy_file_record.file_id],
+        bump = legacy_file_record.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub legacy_file_record: Account<'info, FileRecord>,
+
+    /// The master migrate_legacy_vault created for this vault
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// Target shard (must have capacity)
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(vault_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_shard.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Index page covering the target shard (page = shard_index / 10)
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(vault_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: AccountLoader<'info, UserVaultIndex>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = FileRecord::SIZE,
+        seeds = [FileRecord::SEED_PREFIX, vault_master.key().as_ref(), &legacy_file_record.file_id],
+        bump
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Close the emptied legacy UserVault once every file has been migrated
+#[derive(Accounts)]
+pub struct FinalizeLegacyMigration<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    /// The master the vault's files were moved into: seeded by the vault's id
+    /// and held by the same owner
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+// =============================================================================
+// INSTRUCTION HANDLERS
+// =============================================================================
+
+/// Start a legacy vault migration
+/// Initializes the Vault Master and allocates the Vault Index unless they
+/// already exist; the index is finished by extend_vault_index
+pub fn migrate_legacy_vault(ctx: Context<MigrateLegacyVault>) -> Result<()> {
+    let clock = Clock::get()?;
+    let owner = ctx.accounts.owner.key();
+
+    require!(!ctx.accounts.config.is_paused, BlockDriveError::ProgramPaused);
+    require!(ctx.accounts.vault.is_active(), BlockDriveError::VaultNotActive);
+
+    let vault_master = &mut ctx.accounts.vault_master;
+
+    // init_if_needed leaves existing accounts untouched; only initialize fresh ones
+    let master_created = vault_master.owner == Pubkey::default();
+    if master_created {
+        vault_master.initialize(ctx.bumps.vault_master, owner, clock.unix_timestamp);
+        // Derived from the legacy vault's id, which may predate an ownership change
+        vault_master.vault_id = ctx.accounts.vault.vault_seed();
+        // Carry the key over so migrated files keep their epochs
+        vault_master.master_key_commitment = ctx.accounts.vault.master_key_commitment;
+        vault_master.key_epoch = ctx.accounts.vault.key_epoch;
+    }
+    if ctx.accounts.vault_index.data_is_empty() {
+        let vault_master_key = vault_master.key();
+        allocate_index_page(
+            &ctx.accounts.owner,
+            &ctx.accounts.vault_index,
+            &ctx.accounts.system_program,
+            &[
+                UserVaultIndex::SEED_PREFIX,
+                vault_master_key.as_ref(),
+                &[ctx.bumps.vault_index],
+            ],
+            ctx.program_id,
+        )?;
+    }
+
+    if master_created {
+        emit!(VaultMasterCreated {
+            owner,
+            vault_master: vault_master.key(),
+            vault_index: ctx.accounts.vault_index.key(),
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    msg!(
+        "Legacy migration started: {} files to migrate",
+        ctx.accounts.vault.file_count
+    );
+    Ok(())
+}
+
+/// Migrate one legacy FileRecord into sharded storage
+/// Copies the record under the Vault Master seeds, registers it in the shard
+/// and index, moves the vault totals across and closes the legacy record.
+/// Delegations and versions are seeded by the record address, so each is
+/// re-created under the new record too; remaining accounts hold a
+/// (legacy, new) pair for every delegation, then for every retained version
+pub fn migrate_legacy_file<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MigrateLegacyFile<'info>>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault = &mut ctx.accounts.vault;
+    let legacy_file_record = &ctx.accounts.legacy_file_record;
+    let vault_master = &mut ctx.accounts.vault_master;
+    let mut vault_shard = ctx.accounts.vault_shard.load_mut()?;
+    let mut vault_index = ctx.accounts.vault_index.load_mut()?;
+    let file_record = &mut ctx.accounts.file_record;
+
+    require!(!ctx.accounts.config.is_paused, BlockDriveError::ProgramPaused);
+    require!(vault.is_active(), BlockDriveError::VaultNotActive);
+
+    let delegation_count = legacy_file_record.delegation_count as usize;
+    let version_count = legacy_file_record.retained_versions as usize;
+    require!(
+        ctx.remaining_accounts.len() == 2 * (delegation_count + version_count),
+        BlockDriveError::InvalidMigrationAccounts
+    );
+
+    let file_id = legacy_file_record.file_id;
+    let file_size = legacy_file_record.file_size;
+
+    // Re-create the record under the Vault Master, preserving all metadata
+    file_record.set_inner(FileRecord {
+        bump: ctx.bumps.file_record,
+        vault: vault_master.key(),
+        ..(**legacy_file_record).clone()
+    });
+
+    let shard_index = vault_shard.shard_index;
+    require!(
+        vault_index.covers_shard(shard_index),
+        BlockDriveError::InvalidDirectoryPage
+    );
+
+    let slot_index = vault_shard.add_file(file_record.key(), clock.unix_timestamp)?;
+    vault_index.add_entry(file_id, shard_index, slot_index, clock.unix_timestamp)?;
+
+    // Move totals from the legacy vault to the master
+    vault_master.add_file(file_size, clock.unix_timestamp);
+    vault.remove_file(file_size, clock.unix_timestamp);
+
+    // Shares and versions follow the record. The legacy accounts are closed only
+    // after every create CPI, since a CPI must not follow a direct lamport move
+    // out of an account it does not carry
+    let (delegations, versions) = ctx.remaining_accounts.split_at(2 * delegation_count);
+    let mut moved_delegations = Vec::with_capacity(delegation_count);
+    for pair in delegations.chunks_exact(2) {
+        moved_delegations.push(copy_delegation(
+            &pair[0],
+            &pair[1],
+            legacy_file_record.key(),
+            file_record.key(),
+            &ctx.accounts.owner,
+            &ctx.accounts.system_program,
+            ctx.program_id,
+        )?);
+    }
+    let mut moved_versions = Vec::with_capacity(version_count);
+    for pair in versions.chunks_exact(2) {
+        moved_versions.push(copy_version(
+            &pair[0],
+            &pair[1],
+            legacy_file_record.key(),
+            file_record.key(),
+            &ctx.accounts.owner,
+            &ctx.accounts.system_program,
+            ctx.program_id,
+        )?);
+    }
+    for delegation in moved_delegations {
+        delegation.close(ctx.accounts.owner.to_account_info())?;
+    }
+    for version in moved_versions {
+        version.close(ctx.accounts.owner.to_account_info())?;
+    }
+
+    emit!(LegacyFileMigrated {
+        vault: vault.key(),
+        vault_master: vault_master.key(),
+        legacy_file_record: legacy_file_record.key(),
+        file_record: file_record.key(),
+        file_id,
+        shard_index,
+        slot_index,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Legacy file migrated to shard {} slot {} with {} delegations and {} versions ({} remaining)",
+        shard_index,
+        slot_index,
+        delegation_count,
+        version_count,
+        vault.file_count
+    );
+
+    // Legacy account is closed automatically via `close = owner` constraint
+    Ok(())
+}
+
+/// Finish a legacy migration by closing the empty UserVault
+pub fn finalize_legacy_migration(ctx: Context<FinalizeLegacyMigration>) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault = &ctx.accounts.vault;
+
+    require!(vault.file_count == 0, BlockDriveError::VaultNotEmpty);
+
+    emit!(LegacyVaultMigrated {
+        owner: ctx.accounts.owner.key(),
+        vault: vault.key(),
+        vault_master: ctx.accounts.vault_master.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Legacy vault closed: {}", vault.key());
+
+    // Account is closed automatically via `close = owner` constraint
+    Ok(())
+}
+
+// =============================================================================
+// HELPER FUNCTIONS
+// =============================================================================
+
+/// Re-create a legacy delegation under the migrated record
+/// Returns the legacy delegation for the caller to close into the owner, who
+/// funds the copy, so the grantor's claim on the rent moves with it. A
+/// sub-delegation's parent moves in the same call and keeps its grantee, so its
+/// new address follows from the new record and the child's grantor
+fn copy_delegation<'info>(
+    legacy: &'info AccountInfo<'info>,
+    target: &'info AccountInfo<'info>,
+    legacy_file_record: Pubkey,
+    file_record: Pubkey,
+    owner: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+) -> Result<Account<'info, Delegation>> {
+    let delegation = Account::<Delegation>::try_from(legacy)?;
+    require_keys_eq!(
+        delegation.file_record,
+        legacy_file_record,
+        BlockDriveError::InvalidMigrationAccounts
+    );
+
+    let (expected, bump) = Pubkey::find_program_address(
+        &[Delegation::SEED_PREFIX, file_record.as_ref(), delegation.grantee.as_ref()],
+        program_id,
+    );
+    require_keys_eq!(target.key(), expected, BlockDriveError::InvalidMigrationAccounts);
+
+    let parent_delegation = if delegation.is_reshared() {
+        Pubkey::find_program_address(
+            &[Delegation::SEED_PREFIX, file_record.as_ref(), delegation.grantor.as_ref()],
+            program_id,
+        )
+        .0
+    } else {
+        Pubkey::default()
+    };
+
+    create_pda_account(
+        owner,
+        target,
+        system_program,
+        Delegation::SIZE,
+        Rent::get()?.minimum_balance(Delegation::SIZE),
+        &[
+            Delegation::SEED_PREFIX,
+            file_record.as_ref(),
+            delegation.grantee.as_ref(),
+            &[bump],
+        ],
+        program_id,
+    )?;
+
+    let moved = Delegation {
+        bump,
+        file_record,
+        parent_delegation,
+        ..(*delegation).clone()
+    };
+    let mut data = target.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    moved.try_serialize(&mut writer)?;
+
+    Ok(delegation)
+}
+
+/// Re-create a legacy FileVersion under the migrated record
+/// Returns the legacy version for the caller to close into the owner, who paid
+/// for it and funds the copy
+fn copy_version<'info>(
+    legacy: &'info AccountInfo<'info>,
+    target: &'info AccountInfo<'info>,
+    legacy_file_record: Pubkey,
+    file_record: Pubkey,
+    owner: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+) -> Result<Account<'info, FileVersion>> {
+    let version = Account::<FileVersion>::try_from(legacy)?;
+    require_keys_eq!(
+        version.file_record,
+        legacy_file_record,
+        BlockDriveError::InvalidMigrationAccounts
+    );
+
+    let version_number = version.version.to_le_bytes();
+    let (expected, bump) = Pubkey::find_program_address(
+        &[FileVersion::SEED_PREFIX, file_record.as_ref(), &version_number],
+        program_id,
+    );
+    require_keys_eq!(target.key(), expected, BlockDriveError::InvalidMigrationAccounts);
+
+    create_pda_account(
+        owner,
+        target,
+        system_program,
+        FileVersion::SIZE,
+        Rent::get()?.minimum_balance(FileVersion::SIZE),
+        &[FileVersion::SEED_PREFIX, file_record.as_ref(), &version_number, &[bump]],
+        program_id,
+    )?;
+
+    let moved = FileVersion {
+        bump,
+        file_record,
+        ..(*version).clone()
+    };
+    let mut data = target.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    moved.try_serialize(&mut writer)?;
+
+    Ok(version)
+}
@@ -4,6 +4,10 @@ pub mod delegation;
 pub mod membership;
 pub mod sharding;
 pub mod session;
+pub mod config;
+pub mod migration;
+pub mod ownership;
+pub mod version;
 
 pub use vault::*;
 pub use file::*;
@@ -11,3 +15,7 @@ pub use delegation::*;
 pub use membership::*;
 pub use sharding::*;
 pub use session::*;
+pub use config::*;
+pub use migration::*;
+pub use ownership::*;
+pub use version::*;
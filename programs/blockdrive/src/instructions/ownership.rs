@@ -0,0 +1,893 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use crate::state::{
+    UserVault, UserVaultMaster, UserVaultShard, UserVaultIndex, UserVaultDirectory,
+    FileRecord, Delegation, GuardianSet, RecoveryRequest, OwnerTransfer,
+};
+use crate::errors::BlockDriveError;
+use crate::events::{
+    GuardiansUpdated, RecoveryInitiated, RecoveryApproved, RecoveryExecuted,
+    RecoveryCancelled, OwnerRecordsSynced, MasterKeyRotated,
+    OwnerTransferProposed, OwnerTransferAccepted, OwnerTransferCancelled,
+};
+use crate::instructions::sharding::{upgrade_shard_layout, upgrade_index_layout};
+
+// =============================================================================
+// ACCOUNT CONTEXTS
+// =============================================================================
+
+/// Create the guardian set for a vault
+/// At least one of the UserVault / UserVaultMaster must be passed to prove ownership
+#[derive(Accounts)]
+#[instruction(vault_id: Pubkey)]
+pub struct InitializeGuardians<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = GuardianSet::SIZE,
+        seeds = [GuardianSet::SEED_PREFIX, vault_id.as_ref()],
+        bump
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, vault_id.as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Option<Account<'info, UserVault>>,
+
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_id.as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: Option<Account<'info, UserVaultMaster>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Replace the guardians, threshold or delay
+/// Not while a recovery is pending or the vault is frozen
+#[derive(Accounts)]
+pub struct UpdateGuardians<'info> {
+    #[account(
+        mut,
+        seeds = [GuardianSet::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump = guardian_set.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !guardian_set.pending_recovery @ BlockDriveError::RecoveryPending
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+
+    /// CHECK: The vault id's UserVault address; may not exist (see load_seeded_account)
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: The vault id's UserVaultMaster address; may not exist (see load_seeded_account)
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump
+    )]
+    pub vault_master: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Open a recovery request (signed by a guardian, counts as its approval)
+#[derive(Accounts)]
+pub struct InitiateRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [GuardianSet::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump = guardian_set.bump,
+        constraint = !guardian_set.pending_recovery @ BlockDriveError::RecoveryPending
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+
+    #[account(
+        init,
+        payer = guardian,
+        space = RecoveryRequest::SIZE,
+        seeds = [RecoveryRequest::SEED_PREFIX, guardian_set.key().as_ref()],
+        bump
+    )]
+    pub recovery_request: Account<'info, RecoveryRequest>,
+
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Approve an open recovery request
+#[derive(Accounts)]
+pub struct ApproveRecovery<'info> {
+    #[account(
+        seeds = [GuardianSet::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump = guardian_set.bump
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+
+    #[account(
+        mut,
+        seeds = [RecoveryRequest::SEED_PREFIX, guardian_set.key().as_ref()],
+        bump = recovery_request.bump,
+        has_one = guardian_set
+    )]
+    pub recovery_request: Account<'info, RecoveryRequest>,
+
+    pub guardian: Signer<'info>,
+}
+
+/// Execute an approved recovery once the timelock has elapsed
+/// Both vault addresses are always passed; whichever exists is moved
+#[derive(Accounts)]
+pub struct ExecuteRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [GuardianSet::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump = guardian_set.bump
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+
+    #[account(
+        mut,
+        close = initiator,
+        seeds = [RecoveryRequest::SEED_PREFIX, guardian_set.key().as_ref()],
+        bump = recovery_request.bump,
+        has_one = guardian_set,
+        has_one = initiator,
+        has_one = new_owner @ BlockDriveError::Unauthorized
+    )]
+    pub recovery_request: Account<'info, RecoveryRequest>,
+
+    /// CHECK: The vault id's UserVault address; may not exist (see load_seeded_account)
+    #[account(
+        mut,
+        seeds = [UserVault::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: The vault id's UserVaultMaster address; may not exist (see load_seeded_account)
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump
+    )]
+    pub vault_master: UncheckedAccount<'info>,
+
+    /// CHECK: Guardian that opened the request; receives the rent back
+    #[account(mut)]
+    pub initiator: UncheckedAccount<'info>,
+
+    /// The recovered wallet must sign to prove it can operate the vault
+    pub new_owner: Signer<'info>,
+}
+
+/// Cancel a pending recovery (owner veto during the timelock)
+/// Not while the vault is frozen, so a compromised wallet cannot veto guardians
+#[derive(Accounts)]
+pub struct CancelRecovery<'info> {
+    #[account(
+        mut,
+        seeds = [GuardianSet::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump = guardian_set.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub guardian_set: Account<'info, GuardianSet>,
+
+    #[account(
+        mut,
+        close = initiator,
+        seeds = [RecoveryRequest::SEED_PREFIX, guardian_set.key().as_ref()],
+        bump = recovery_request.bump,
+        has_one = guardian_set,
+        has_one = initiator
+    )]
+    pub recovery_request: Account<'info, RecoveryRequest>,
+
+    /// CHECK: The vault id's UserVault address; may not exist (see load_seeded_account)
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: The vault id's UserVaultMaster address; may not exist (see load_seeded_account)
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump
+    )]
+    pub vault_master: UncheckedAccount<'info>,
+
+    /// CHECK: Guardian that opened the request; receives the rent back
+    #[account(mut)]
+    pub initiator: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Propose moving a vault to a new wallet
+/// Not while the vault is frozen or a guardian recovery is pending.
+/// Both vault addresses are always passed; each that exists must be the signer's
+#[derive(Accounts)]
+#[instruction(vault_id: Pubkey)]
+pub struct ProposeOwnerTransfer<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = OwnerTransfer::SIZE,
+        seeds = [OwnerTransfer::SEED_PREFIX, vault_id.as_ref()],
+        bump
+    )]
+    pub owner_transfer: Account<'info, OwnerTransfer>,
+
+    /// CHECK: The vault id's UserVault address; may not exist (see load_seeded_account)
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, vault_id.as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: The vault id's UserVaultMaster address; may not exist (see load_seeded_account)
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_id.as_ref()],
+        bump
+    )]
+    pub vault_master: UncheckedAccount<'info>,
+
+    /// CHECK: The vault id's GuardianSet address; may not exist (see load_seeded_account)
+    #[account(
+        seeds = [GuardianSet::SEED_PREFIX, vault_id.as_ref()],
+        bump
+    )]
+    pub guardian_set: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accept a pending ownership transfer (signed by the new owner)
+/// Both vault addresses are always passed; whichever exists is moved.
+/// Fails while the vault is frozen or a guardian recovery is pending
+#[derive(Accounts)]
+pub struct AcceptOwnerTransfer<'info> {
+    #[account(
+        mut,
+        close = current_owner,
+        seeds = [OwnerTransfer::SEED_PREFIX, owner_transfer.vault_id.as_ref()],
+        bump = owner_transfer.bump,
+        has_one = current_owner,
+        has_one = new_owner @ BlockDriveError::Unauthorized
+    )]
+    pub owner_transfer: Account<'info, OwnerTransfer>,
+
+    /// CHECK: The vault id's UserVault address; may not exist (see load_seeded_account)
+    #[account(
+        mut,
+        seeds = [UserVault::SEED_PREFIX, owner_transfer.vault_id.as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+
+    /// CHECK: The vault id's UserVaultMaster address; may not exist (see load_seeded_account)
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, owner_transfer.vault_id.as_ref()],
+        bump
+    )]
+    pub vault_master: UncheckedAccount<'info>,
+
+    /// CHECK: The vault id's GuardianSet address; may not exist (see load_seeded_account).
+    /// When it does, it follows the vault to the new owner
+    #[account(
+        mut,
+        seeds = [GuardianSet::SEED_PREFIX, owner_transfer.vault_id.as_ref()],
+        bump
+    )]
+    pub guardian_set: UncheckedAccount<'info>,
+
+    /// CHECK: Proposing owner; receives the proposal rent back
+    #[account(mut)]
+    pub current_owner: UncheckedAccount<'info>,
+
+    pub new_owner: Signer<'info>,
+}
+
+/// Withdraw a pending ownership transfer
+#[derive(Accounts)]
+pub struct CancelOwnerTransfer<'info> {
+    #[account(
+        mut,
+        close = current_owner,
+        seeds = [OwnerTransfer::SEED_PREFIX, owner_transfer.vault_id.as_ref()],
+        bump = owner_transfer.bump,
+        has_one = current_owner @ BlockDriveError::Unauthorized
+    )]
+    pub owner_transfer: Account<'info, OwnerTransfer>,
+
+    #[account(mut)]
+    pub current_owner: Signer<'info>,
+}
+
+/// Re-point owner fields of vault records at the vault's current owner
+/// Records are passed as remaining accounts (see sync_owner_records)
+#[derive(Accounts)]
+pub struct SyncOwnerRecords<'info> {
+    #[account(
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Option<Account<'info, UserVault>>,
+
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: Option<Account<'info, UserVaultMaster>>,
+
+    pub owner: Signer<'info>,
+}
+
+// =============================================================================
+// INSTRUCTION HANDLERS
+// =============================================================================
+
+/// Create the guardian set for a vault
+pub fn initialize_guardians(
+    ctx: Context<InitializeGuardians>,
+    vault_id: Pubkey,
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    recovery_delay: i64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let owner = ctx.accounts.owner.key();
+
+    require!(
+        ctx.accounts.vault.is_some() || ctx.accounts.vault_master.is_some(),
+        BlockDriveError::VaultAccountRequired
+    );
+
+    let guardian_set = &mut ctx.accounts.guardian_set;
+    guardian_set.bump = ctx.bumps.guardian_set;
+    guardian_set.vault_id = vault_id;
+    guardian_set.owner = owner;
+    guardian_set.pending_recovery = false;
+    guardian_set.created_at = clock.unix_timestamp;
+    guardian_set.reserved = [0u8; 32];
+    guardian_set.set_guardians(&guardians, threshold, recovery_delay, clock.unix_timestamp)?;
+
+    emit!(GuardiansUpdated {
+        guardian_set: guardian_set.key(),
+        vault_id,
+        owner,
+        guardian_count: guardian_set.guardian_count,
+        threshold,
+        recovery_delay,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Guardian set created: {} of {}", threshold, guardian_set.guardian_count);
+    Ok(())
+}
+
+/// Replace the guardian list, threshold and delay
+pub fn update_guardians(
+    ctx: Context<UpdateGuardians>,
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    recovery_delay: i64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    require_vault_id_not_frozen(&ctx.accounts.vault, &ctx.accounts.vault_master)?;
+
+    let guardian_set = &mut ctx.accounts.guardian_set;
+    guardian_set.set_guardians(&guardians, threshold, recovery_delay, clock.unix_timestamp)?;
+
+    emit!(GuardiansUpdated {
+        guardian_set: guardian_set.key(),
+        vault_id: guardian_set.vault_id,
+        owner: guardian_set.owner,
+        guardian_count: guardian_set.guardian_count,
+        threshold,
+        recovery_delay,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Guardian set updated: {} of {}", threshold, guardian_set.guardian_count);
+    Ok(())
+}
+
+/// Open a recovery request to move the vault to `new_owner`
+/// The initiating guardian's approval is recorded immediately
+pub fn initiate_recovery(
+    ctx: Context<InitiateRecovery>,
+    new_owner: Pubkey,
+    new_master_key_commitment: [u8; 32],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let guardian_set = &mut ctx.accounts.guardian_set;
+    let recovery_request = &mut ctx.accounts.recovery_request;
+    let guardian = ctx.accounts.guardian.key();
+
+    let position = guardian_set
+        .guardian_position(&guardian)
+        .ok_or(BlockDriveError::NotAGuardian)?;
+
+    require!(
+        new_owner != Pubkey::default() && new_owner != guardian_set.owner,
+        BlockDriveError::InvalidNewOwner
+    );
+
+    recovery_request.bump = ctx.bumps.recovery_request;
+    recovery_request.guardian_set = guardian_set.key();
+    recovery_request.old_owner = guardian_set.owner;
+    recovery_request.new_owner = new_owner;
+    recovery_request.new_master_key_commitment = new_master_key_commitment;
+    recovery_request.initiator = guardian;
+    recovery_request.approvals = 0;
+    recovery_request.approval_count = 0;
+    recovery_request.initiated_at = clock.unix_timestamp;
+    recovery_request.executable_at = 0;
+    recovery_request.reserved = [0u8; 32];
+    recovery_request.approve(
+        position,
+        guardian_set.threshold,
+        guardian_set.recovery_delay,
+        clock.unix_timestamp,
+    )?;
+
+    guardian_set.pending_recovery = true;
+    guardian_set.updated_at = clock.unix_timestamp;
+
+    emit!(RecoveryInitiated {
+        guardian_set: guardian_set.key(),
+        recovery_request: recovery_request.key(),
+        vault_id: guardian_set.vault_id,
+        initiator: guardian,
+        new_owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Recovery initiated ({} of {} approvals)",
+        recovery_request.approval_count,
+        guardian_set.threshold
+    );
+    Ok(())
+}
+
+/// Approve a pending recovery
+pub fn approve_recovery(ctx: Context<ApproveRecovery>) -> Result<()> {
+    let clock = Clock::get()?;
+    let guardian_set = &ctx.accounts.guardian_set;
+    let recovery_request = &mut ctx.accounts.recovery_request;
+    let guardian = ctx.accounts.guardian.key();
+
+    let position = guardian_set
+        .guardian_position(&guardian)
+        .ok_or(BlockDriveError::NotAGuardian)?;
+
+    recovery_request.approve(
+        position,
+        guardian_set.threshold,
+        guardian_set.recovery_delay,
+        clock.unix_timestamp,
+    )?;
+
+    emit!(RecoveryApproved {
+        recovery_request: recovery_request.key(),
+        guardian,
+        approval_count: recovery_request.approval_count,
+        executable_at: recovery_request.executable_at,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Recovery approved ({} of {} approvals)",
+        recovery_request.approval_count,
+        guardian_set.threshold
+    );
+    Ok(())
+}
+
+/// Execute an approved recovery
+/// Moves the vault accounts to the new owner and rotates the master key commitment
+/// (and key epoch) on each of them.
+/// FileRecords and shard/index pages are re-pointed afterwards with sync_owner_records.
+pub fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+    let clock = Clock::get()?;
+    let guardian_set = &mut ctx.accounts.guardian_set;
+    let recovery_request = &ctx.accounts.recovery_request;
+    let old_owner = recovery_request.old_owner;
+    let new_owner = recovery_request.new_owner;
+
+    require!(
+        recovery_request.is_executable(guardian_set.threshold, clock.unix_timestamp),
+        BlockDriveError::RecoveryNotExecutable
+    );
+
+    let mut vault = load_seeded_account::<UserVault>(&ctx.accounts.vault)?;
+    let mut vault_master = load_seeded_account::<UserVaultMaster>(&ctx.accounts.vault_master)?;
+    require!(
+        vault.is_some() || vault_master.is_some(),
+        BlockDriveError::VaultAccountRequired
+    );
+
+    if let Some(vault) = vault.as_mut() {
+        require_keys_eq!(vault.owner, old_owner, BlockDriveError::Unauthorized);
+        vault.set_owner(new_owner, clock.unix_timestamp);
+        vault.rotate_master_key(recovery_request.new_master_key_commitment, clock.unix_timestamp);
+        store_seeded_account(&ctx.accounts.vault, vault)?;
+
+        emit!(MasterKeyRotated {
+            vault: ctx.accounts.vault.key(),
+            key_epoch: vault.key_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+    if let Some(vault_master) = vault_master.as_mut() {
+        require_keys_eq!(vault_master.owner, old_owner, BlockDriveError::Unauthorized);
+        vault_master.set_owner(new_owner, clock.unix_timestamp);
+        vault_master.rotate_master_key(
+            recovery_request.new_master_key_commitment,
+            clock.unix_timestamp,
+        );
+        store_seeded_account(&ctx.accounts.vault_master, vault_master)?;
+
+        emit!(MasterKeyRotated {
+            vault: ctx.accounts.vault_master.key(),
+            key_epoch: vault_master.key_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    guardian_set.owner = new_owner;
+    guardian_set.pending_recovery = false;
+    guardian_set.updated_at = clock.unix_timestamp;
+
+    emit!(RecoveryExecuted {
+        guardian_set: guardian_set.key(),
+        vault_id: guardian_set.vault_id,
+        old_owner,
+        new_owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Recovery executed: vault moved to {}", new_owner);
+
+    // Request is closed automatically via `close = initiator` constraint
+    Ok(())
+}
+
+/// Cancel a pending recovery
+pub fn cancel_recovery(ctx: Context<CancelRecovery>) -> Result<()> {
+    let clock = Clock::get()?;
+    require_vault_id_not_frozen(&ctx.accounts.vault, &ctx.accounts.vault_master)?;
+
+    let guardian_set = &mut ctx.accounts.guardian_set;
+
+    guardian_set.pending_recovery = false;
+    guardian_set.updated_at = clock.unix_timestamp;
+
+    emit!(RecoveryCancelled {
+        guardian_set: guardian_set.key(),
+        recovery_request: ctx.accounts.recovery_request.key(),
+        owner: ctx.accounts.owner.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Recovery cancelled by owner");
+
+    // Request is closed automatically via `close = initiator` constraint
+    Ok(())
+}
+
+/// Propose transferring a vault to `new_owner`
+pub fn propose_owner_transfer(
+    ctx: Context<ProposeOwnerTransfer>,
+    vault_id: Pubkey,
+    new_owner: Pubkey,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let owner = ctx.accounts.owner.key();
+
+    let vault = load_seeded_account::<UserVault>(&ctx.accounts.vault)?;
+    let vault_master = load_seeded_account::<UserVaultMaster>(&ctx.accounts.vault_master)?;
+    require!(
+        vault.is_some() || vault_master.is_some(),
+        BlockDriveError::VaultAccountRequired
+    );
+    if let Some(vault) = vault.as_ref() {
+        require_keys_eq!(vault.owner, owner, BlockDriveError::Unauthorized);
+        require!(!vault.is_frozen(), BlockDriveError::VaultFrozen);
+    }
+    if let Some(vault_master) = vault_master.as_ref() {
+        require_keys_eq!(vault_master.owner, owner, BlockDriveError::Unauthorized);
+        require!(!vault_master.is_frozen(), BlockDriveError::VaultFrozen);
+    }
+    if let Some(guardian_set) = load_seeded_account::<GuardianSet>(&ctx.accounts.guardian_set)? {
+        require!(!guardian_set.pending_recovery, BlockDriveError::RecoveryPending);
+    }
+    require!(
+        new_owner != Pubkey::default() && new_owner != owner,
+        BlockDriveError::InvalidNewOwner
+    );
+
+    let owner_transfer = &mut ctx.accounts.owner_transfer;
+    owner_transfer.bump = ctx.bumps.owner_transfer;
+    owner_transfer.vault_id = vault_id;
+    owner_transfer.current_owner = owner;
+    owner_transfer.new_owner = new_owner;
+    owner_transfer.proposed_at = clock.unix_timestamp;
+    owner_transfer.reserved = [0u8; 32];
+
+    emit!(OwnerTransferProposed {
+        owner_transfer: owner_transfer.key(),
+        vault_id,
+        current_owner: owner,
+        new_owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Ownership transfer proposed to {}", new_owner);
+    Ok(())
+}
+
+/// Accept a pending transfer
+/// Moves the vault accounts and guardian set to the new owner and rotates each
+/// vault account to the new wallet's master key commitment. Records passed as remaining accounts
+/// are re-pointed in the same transaction; the rest via sync_owner_records.
+pub fn accept_owner_transfer<'info>(
+    ctx: Context<'_, '_, 'info, 'info, AcceptOwnerTransfer<'info>>,
+    new_master_key_commitment: [u8; 32],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault_id = ctx.accounts.owner_transfer.vault_id;
+    let old_owner = ctx.accounts.owner_transfer.current_owner;
+    let new_owner = ctx.accounts.new_owner.key();
+
+    let mut vault = load_seeded_account::<UserVault>(&ctx.accounts.vault)?;
+    let mut vault_master = load_seeded_account::<UserVaultMaster>(&ctx.accounts.vault_master)?;
+    require!(
+        vault.is_some() || vault_master.is_some(),
+        BlockDriveError::VaultAccountRequired
+    );
+
+    let mut vault_key = None;
+    if let Some(vault) = vault.as_mut() {
+        require_keys_eq!(vault.owner, old_owner, BlockDriveError::Unauthorized);
+        require!(!vault.is_frozen(), BlockDriveError::VaultFrozen);
+        vault.set_owner(new_owner, clock.unix_timestamp);
+        vault.rotate_master_key(new_master_key_commitment, clock.unix_timestamp);
+        store_seeded_account(&ctx.accounts.vault, vault)?;
+        vault_key = Some(ctx.accounts.vault.key());
+
+        emit!(MasterKeyRotated {
+            vault: ctx.accounts.vault.key(),
+            key_epoch: vault.key_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+    let mut vault_master_key = None;
+    if let Some(vault_master) = vault_master.as_mut() {
+        require_keys_eq!(vault_master.owner, old_owner, BlockDriveError::Unauthorized);
+        require!(!vault_master.is_frozen(), BlockDriveError::VaultFrozen);
+        vault_master.set_owner(new_owner, clock.unix_timestamp);
+        vault_master.rotate_master_key(new_master_key_commitment, clock.unix_timestamp);
+        store_seeded_account(&ctx.accounts.vault_master, vault_master)?;
+        vault_master_key = Some(ctx.accounts.vault_master.key());
+
+        emit!(MasterKeyRotated {
+            vault: ctx.accounts.vault_master.key(),
+            key_epoch: vault_master.key_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+    if let Some(mut guardian_set) = load_seeded_account::<GuardianSet>(&ctx.accounts.guardian_set)? {
+        require!(!guardian_set.pending_recovery, BlockDriveError::RecoveryPending);
+        guardian_set.owner = new_owner;
+        guardian_set.updated_at = clock.unix_timestamp;
+        store_seeded_account(&ctx.accounts.guardian_set, &guardian_set)?;
+    }
+
+    emit!(OwnerTransferAccepted {
+        vault_id,
+        vault: vault_key.unwrap_or_default(),
+        vault_master: vault_master_key.unwrap_or_default(),
+        old_owner,
+        new_owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if !ctx.remaining_accounts.is_empty() {
+        let records_updated = sync_owner_batch(
+            ctx.remaining_accounts,
+            vault_key,
+            vault_master_key,
+            new_owner,
+            ctx.program_id,
+        )?;
+
+        emit!(OwnerRecordsSynced {
+            vault_id,
+            owner: new_owner,
+            records_updated,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    msg!("Ownership transferred from {} to {}", old_owner, new_owner);
+
+    // Proposal is closed automatically via `close = current_owner` constraint
+    Ok(())
+}
+
+/// Withdraw a pending ownership transfer
+pub fn cancel_owner_transfer(ctx: Context<CancelOwnerTransfer>) -> Result<()> {
+    let clock = Clock::get()?;
+    let owner_transfer = &ctx.accounts.owner_transfer;
+
+    emit!(OwnerTransferCancelled {
+        owner_transfer: owner_transfer.key(),
+        vault_id: owner_transfer.vault_id,
+        owner: owner_transfer.current_owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Ownership transfer cancelled");
+
+    // Proposal is closed automatically via `close = current_owner` constraint
+    Ok(())
+}
+
+/// Re-point a batch of records at the vault's current owner
+/// Accepts FileRecords, Delegations, UserVaultShards, UserVaultIndex pages and
+/// UserVaultDirectory pages as remaining accounts
+pub fn sync_owner_records<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SyncOwnerRecords<'info>>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let owner = ctx.accounts.owner.key();
+
+    let vault = ctx.accounts.vault.as_ref().map(|v| (v.key(), v.vault_seed()));
+    let vault_master = ctx.accounts.vault_master.as_ref().map(|v| (v.key(), v.vault_seed()));
+    let (_, vault_id) = vault
+        .or(vault_master)
+        .ok_or(BlockDriveError::VaultAccountRequired)?;
+
+    let records_updated = sync_owner_batch(
+        ctx.remaining_accounts,
+        vault.map(|(key, _)| key),
+        vault_master.map(|(key, _)| key),
+        owner,
+        ctx.program_id,
+    )?;
+
+    emit!(OwnerRecordsSynced {
+        vault_id,
+        owner,
+        records_updated,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Owner synced on {} records", records_updated);
+    Ok(())
+}
+
+// =============================================================================
+// HELPER FUNCTIONS
+// =============================================================================
+
+/// Read a vault or guardian account at a seed-derived address
+/// Returns None when nothing has been created there, so a caller cannot leave
+/// out an account that exists
+fn load_seeded_account<T: AccountDeserialize>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(*info.owner, crate::ID, BlockDriveError::Unauthorized);
+
+    let data = info.try_borrow_data()?;
+    Ok(Some(T::try_deserialize(&mut &data[..])?))
+}
+
+/// Reject the operation while either vault account of a vault id is frozen
+/// A frozen vault can still be recovered, but not defended by its current wallet
+fn require_vault_id_not_frozen(vault: &AccountInfo, vault_master: &AccountInfo) -> Result<()> {
+    if let Some(vault) = load_seeded_account::<UserVault>(vault)? {
+        require!(!vault.is_frozen(), BlockDriveError::VaultFrozen);
+    }
+    if let Some(vault_master) = load_seeded_account::<UserVaultMaster>(vault_master)? {
+        require!(!vault_master.is_frozen(), BlockDriveError::VaultFrozen);
+    }
+    Ok(())
+}
+
+/// Write back an account read with load_seeded_account
+fn store_seeded_account<T: AccountSerialize>(info: &AccountInfo, account: &T) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    account.try_serialize(&mut writer)
+}
+
+/// Write `new_owner` into every record in `accounts` that belongs to the vault
+/// A Delegation must follow its FileRecord in the same batch (synced earlier or
+/// not). Only the file owner grants at depth 0, so any depth-0 grantor other than
+/// `new_owner` is a previous owner, however many transfers ago; sub-delegations
+/// belong to their re-sharing grantee and are left alone. Shards and index pages
+/// still in the earlier Borsh layout are converted on the way
+fn sync_owner_batch<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    vault: Option<Pubkey>,
+    vault_master: Option<Pubkey>,
+    new_owner: Pubkey,
+    program_id: &Pubkey,
+) -> Result<u32> {
+    let belongs = |key: Pubkey| Some(key) == vault || Some(key) == vault_master;
+    let mut synced_files: Vec<Pubkey> = Vec::new();
+    let mut records_updated: u32 = 0;
+
+    for info in accounts.iter() {
+        let discriminator: [u8; 8] = {
+            let data = info.try_borrow_data()?;
+            require!(data.len() >= 8, ErrorCode::AccountDiscriminatorNotFound);
+            data[..8].try_into().unwrap()
+        };
+
+        if discriminator == FileRecord::DISCRIMINATOR {
+            let mut file_record = Account::<FileRecord>::try_from(info)?;
+            require!(belongs(file_record.vault), BlockDriveError::Unauthorized);
+            synced_files.push(file_record.key());
+            file_record.owner = new_owner;
+            file_record.exit(program_id)?;
+        } else if discriminator == Delegation::DISCRIMINATOR {
+            let mut delegation = Account::<Delegation>::try_from(info)?;
+            require!(
+                synced_files.contains(&delegation.file_record),
+                BlockDriveError::Unauthorized
+            );
+            if !delegation.needs_owner_sync(&new_owner) {
+                continue;
+            }
+            delegation.grantor = new_owner;
+            delegation.exit(program_id)?;
+        } else if discriminator == UserVaultShard::DISCRIMINATOR {
+            upgrade_shard_layout(info)?;
+            let loader = AccountLoader::<UserVaultShard>::try_from(info)?;
+            let mut vault_shard = loader.load_mut()?;
+            require!(belongs(vault_shard.vault_master), BlockDriveError::Unauthorized);
+            vault_shard.owner = new_owner;
+        } else if discriminator == UserVaultIndex::DISCRIMINATOR {
+            upgrade_index_layout(info)?;
+            let loader = AccountLoader::<UserVaultIndex>::try_from(info)?;
+            let mut vault_index = loader.load_mut()?;
+            require!(belongs(vault_index.vault_master), BlockDriveError::Unauthorized);
+            vault_index.owner = new_owner;
+        } else if discriminator == UserVaultDirectory::DISCRIMINATOR {
+            let mut vault_directory = Account::<UserVaultDirectory>::try_from(info)?;
+            require!(belongs(vault_directory.vault_master), BlockDriveError::Unauthorized);
+            vault_directory.owner = new_owner;
+            vault_directory.exit(program_id)?;
+        } else {
+            return err!(ErrorCode::AccountDiscriminatorMismatch);
+        }
+
+        records_updated = records_updated.saturating_add(1);
+    }
+
+    Ok(records_updated)
+}
@@ -1,7 +1,11 @@
 use anchor_lang::prelude::*;
-use crate::state::{SessionDelegation, DEFAULT_SESSION_DURATION};
+use crate::state::{SessionDelegation, VaultConfig, DEFAULT_SESSION_DURATION};
 use crate::errors::BlockDriveError;
-use crate::events::{SessionDelegationCreated, SessionDelegationRevoked, SessionDelegationExtended};
+use crate::events::{
+    SessionDelegationCreated, SessionDelegationRevoked, SessionDelegationExtended,
+    ExpiredSessionClosed,
+};
+use crate::instructions::config::pay_cleanup_tip;
 
 // =============================================================================
 // ACCOUNT CONTEXTS
@@ -90,6 +94,39 @@ pub struct CloseSession<'info> {
     pub owner: Signer<'info>,
 }
 
+/// Close an expired or revoked session (anyone can call)
+#[derive(Accounts)]
+pub struct CloseExpiredSession<'info> {
+    #[account(
+        mut,
+        seeds = [
+            SessionDelegation::SEED_PREFIX,
+            owner.key().as_ref(),
+            session.relayer.as_ref()
+        ],
+        bump = session.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        close = owner
+    )]
+    pub session: Account<'info, SessionDelegation>,
+
+    /// CHECK: Paid the session's rent and receives it back; verified via has_one
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Pays the cleanup tip out of its balance above rent
+    #[account(
+        mut,
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// Any wallet; receives the cleanup tip
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+}
+
 /// Validate a session for relayer operations (read-only check)
 #[derive(Accounts)]
 pub struct ValidateSession<'info> {
@@ -225,6 +262,35 @@ pub fn close_session(ctx: Context<CloseSession>) -> Result<()> {
     Ok(())
 }
 
+/// Close an expired or revoked session and return its rent to the owner
+/// Permissionless; the cranker receives config.cleanup_tip from the tip pool
+pub fn close_expired_session(ctx: Context<CloseExpiredSession>) -> Result<()> {
+    let clock = Clock::get()?;
+    let session = &ctx.accounts.session;
+
+    require!(
+        !session.is_valid(clock.unix_timestamp),
+        BlockDriveError::SessionStillActive
+    );
+
+    let tip = pay_cleanup_tip(
+        &ctx.accounts.config,
+        &ctx.accounts.cranker.to_account_info(),
+    )?;
+
+    emit!(ExpiredSessionClosed {
+        session: session.key(),
+        owner: session.owner,
+        relayer: session.relayer,
+        cranker: ctx.accounts.cranker.key(),
+        tip,
+        timestamp: clock.unix_timestamp,
+    });
+
+    // Account will be closed automatically via `close = owner` constraint
+    Ok(())
+}
+
 /// Validate that a session is active and can perform an operation
 /// This is a helper instruction that can be called by relayers to check status
 /// Returns true via program logs, or errors if invalid
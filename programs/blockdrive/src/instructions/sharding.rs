@@ -1,14 +1,22 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::Discriminator;
 use crate::state::{
-    UserVaultMaster, UserVaultShard, UserVaultIndex,
-    IndexEntry, MAX_SHARDS, FILES_PER_SHARD, MAX_FILES_PER_SHARD,
-    FileRecord, FileStatus, SecurityLevel,
+    UserVaultMaster, UserVaultShard, UserVaultIndex, UserVaultDirectory, VaultStatus,
+    FileLocation, ShardStatus, FileRecord, FileStatus, FileRegistration, VaultConfig,
 };
 use crate::errors::BlockDriveError;
 use crate::events::{
-    VaultMasterCreated, ShardCreated, FileRegisteredSharded,
-    VaultIndexCreated,
+    VaultMasterCreated, ShardCreated, FileRegisteredSharded, FileDeletedSharded,
+    FileStorageUpdated, FileArchived, FileUnarchived,
+    VaultIndexCreated, DirectoryPageCreated, FeeType,
+    ShardCompacted, FileRebalanced, ShardArchived, ShardClosed, VaultMasterClosed,
+    VaultFrozen, VaultUnfrozen, MasterKeyRotated, FileReencrypted, FreezeReason,
 };
+use crate::instructions::config::collect_fee;
+use crate::instructions::delegation::revoke_all_delegations;
+use crate::instructions::file::collect_stale_files;
 
 // =============================================================================
 // ACCOUNT CONTEXTS
@@ -26,14 +34,19 @@ pub struct InitializeVaultMaster<'info> {
     )]
     pub vault_master: Account<'info, UserVaultMaster>,
 
+    /// CHECK: Allocated in the handler; extend_vault_index grows and initializes it
     #[account(
-        init,
-        payer = owner,
-        space = UserVaultIndex::MAX_SIZE,
+        mut,
         seeds = [UserVaultIndex::SEED_PREFIX, vault_master.key().as_ref()],
         bump
     )]
-    pub vault_index: Account<'info, UserVaultIndex>,
+    pub vault_index: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
 
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -47,9 +60,10 @@ pub struct InitializeVaultMaster<'info> {
 pub struct CreateShard<'info> {
     #[account(
         mut,
-        seeds = [UserVaultMaster::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
         bump = vault_master.bump,
-        has_one = owner @ BlockDriveError::Unauthorized
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
     )]
     pub vault_master: Account<'info, UserVaultMaster>,
 
@@ -64,270 +78,1702 @@ pub struct CreateShard<'info> {
         ],
         bump
     )]
-    pub vault_shard: Account<'info, UserVaultShard>,
+    pub vault_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Directory page covering the new shard (required for shard index 10+)
+    #[account(
+        mut,
+        constraint = vault_directory.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_directory: Option<Account<'info, UserVaultDirectory>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Add an overflow directory page and its index page (10 more shards)
+#[derive(Accounts)]
+#[instruction(page_index: u8)]
+pub struct CreateDirectoryPage<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = UserVaultDirectory::SIZE,
+        seeds = [
+            UserVaultDirectory::SEED_PREFIX,
+            vault_master.key().as_ref(),
+            &[page_index]
+        ],
+        bump
+    )]
+    pub vault_directory: Account<'info, UserVaultDirectory>,
+
+    /// CHECK: Allocated in the handler; extend_vault_index grows and initializes it
+    #[account(
+        mut,
+        seeds = [
+            UserVaultIndex::SEED_PREFIX,
+            vault_master.key().as_ref(),
+            &[page_index]
+        ],
+        bump
+    )]
+    pub vault_index: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grow a freshly allocated index page to full size and initialize it
+#[derive(Accounts)]
+#[instruction(page_index: u8)]
+pub struct ExtendVaultIndex<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// CHECK: Program ownership is enforced here; the address is checked against
+    /// the page's PDA in the handler, since page 0 uses different seeds
+    #[account(
+        mut,
+        owner = crate::ID @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Register a file to a specific shard (sharded version of register_file)
+#[derive(Accounts)]
+#[instruction(file_id: [u8; 16], shard_index: u8)]
+pub struct RegisterFileSharded<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(vault_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        seeds = [
+            UserVaultShard::SEED_PREFIX,
+            vault_master.key().as_ref(),
+            &[shard_index]
+        ],
+        bump = vault_shard.load()?.bump,
+        constraint = vault_shard.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Index page covering the target shard (page = shard_index / 10)
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(vault_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: AccountLoader<'info, UserVaultIndex>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = FileRecord::SIZE,
+        seeds = [FileRecord::SEED_PREFIX, vault_master.key().as_ref(), &file_id],
+        bump
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// CHECK: Receives the registration fee; verified against config.fee_collector
+    #[account(
+        mut,
+        address = config.fee_collector @ BlockDriveError::Unauthorized
+    )]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Register a file to the active shard, chosen by the program
+/// When the active shard is full, a later shard with free slots takes over, or a
+/// new shard is created on the fly (funded by payer)
+#[derive(Accounts)]
+#[instruction(file_id: [u8; 16])]
+pub struct RegisterFileAuto<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// Shard at vault_master.active_shard_index (omit when no shards are open)
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(active_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        seeds = [
+            UserVaultShard::SEED_PREFIX,
+            vault_master.key().as_ref(),
+            &[vault_master.active_shard_index]
+        ],
+        bump = active_shard.load()?.bump
+    )]
+    pub active_shard: Option<AccountLoader<'info, UserVaultShard>>,
+
+    /// CHECK: Only used when the active shard is full: either an existing shard
+    /// after the active one with free slots, or the uncreated shard at index
+    /// vault_master.total_shards. Ownership / PDA checked in the handler
+    #[account(mut)]
+    pub next_shard: Option<UncheckedAccount<'info>>,
+
+    /// Index page covering the active shard
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(vault_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: AccountLoader<'info, UserVaultIndex>,
+
+    /// Index page covering the next shard, when it starts a new page
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(next_vault_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = next_vault_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub next_vault_index: Option<AccountLoader<'info, UserVaultIndex>>,
+
+    /// Directory page covering the next shard (required when creating shard 10+)
+    #[account(
+        mut,
+        constraint = vault_directory.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_directory: Option<Account<'info, UserVaultDirectory>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = FileRecord::SIZE,
+        seeds = [FileRecord::SEED_PREFIX, vault_master.key().as_ref(), &file_id],
+        bump
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    /// CHECK: Receives the registration fee; verified against config.fee_collector
+    #[account(
+        mut,
+        address = config.fee_collector @ BlockDriveError::Unauthorized
+    )]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Funds the rent of a newly created shard
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Delete a sharded file record, freeing its shard slot and index entry
+#[derive(Accounts)]
+pub struct DeleteFileSharded<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// The shard holding the file (verified against the index in the handler)
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(vault_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_shard.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Index page covering the target shard (page = shard_index / 10)
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(vault_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: AccountLoader<'info, UserVaultIndex>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [FileRecord::SEED_PREFIX, vault_master.key().as_ref(), &file_record.file_id],
+        bump = file_record.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
 
     #[account(mut)]
     pub owner: Signer<'info>,
+}
+
+/// Pack a shard's file records into a dense prefix, keeping the index in sync
+#[derive(Accounts)]
+pub struct CompactShard<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(vault_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_shard.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Index page covering the shard (page = shard_index / 10)
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(vault_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: AccountLoader<'info, UserVaultIndex>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Move a file record from one shard to another, keeping the index in sync
+#[derive(Accounts)]
+pub struct RebalanceFile<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = file_record.vault == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    /// Shard currently holding the file (verified against the index in the handler)
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(source_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = source_shard.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub source_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Shard receiving the file (must have capacity)
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(target_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = target_shard.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized,
+        constraint = target_shard.key() != source_shard.key() @ BlockDriveError::InvalidShardIndex
+    )]
+    pub target_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Index page covering the source shard
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(source_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = source_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub source_index: AccountLoader<'info, UserVaultIndex>,
+
+    /// Index page covering the target shard, when it differs from the source page
+    #[account(
+        mut,
+        constraint = UserVaultIndex::has_current_layout(target_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = target_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized,
+        constraint = target_index.key() != source_index.key() @ BlockDriveError::InvalidDirectoryPage
+    )]
+    pub target_index: Option<AccountLoader<'info, UserVaultIndex>>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Update a sharded file's storage with redundancy info
+#[derive(Accounts)]
+pub struct UpdateFileStorageSharded<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = file_record.vault == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Archive a sharded file
+#[derive(Accounts)]
+pub struct ArchiveFileSharded<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = file_record.vault == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Restore an archived sharded file
+#[derive(Accounts)]
+pub struct UnarchiveFileSharded<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = file_record.vault == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Archive a shard so it stops accepting new files
+#[derive(Accounts)]
+pub struct ArchiveShard<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        mut,
+        constraint = UserVaultShard::has_current_layout(vault_shard.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_shard.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_shard: AccountLoader<'info, UserVaultShard>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Close an empty shard and reclaim its rent
+#[derive(Accounts)]
+pub struct CloseShard<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// Either layout; a legacy shard is converted before it is read
+    #[account(
+        mut,
+        close = owner
+    )]
+    pub vault_shard: AccountLoader<'info, UserVaultShard>,
+
+    /// Directory page covering the shard (required for shard index 10+)
+    #[account(
+        mut,
+        constraint = vault_directory.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_directory: Option<Account<'info, UserVaultDirectory>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Close an empty Vault Master with its base index page
+/// Overflow pages are passed as remaining accounts:
+/// [directory page 1, index page 1, directory page 2, index page 2, ...]
+#[derive(Accounts)]
+pub struct CloseVaultMaster<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// Either layout; only the address is checked, so a legacy index need not
+    /// be converted first
+    #[account(
+        mut,
+        close = owner,
+        seeds = [UserVaultIndex::SEED_PREFIX, vault_master.key().as_ref()],
+        bump
+    )]
+    pub vault_index: AccountLoader<'info, UserVaultIndex>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+/// Query the vault index to find a file's location
+/// Read-only and signer-free so clients can simulate it and programs can CPI into it
+#[derive(Accounts)]
+pub struct QueryFileLocation<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    /// Index page to search (page = shard_index / 10)
+    #[account(
+        constraint = UserVaultIndex::has_current_layout(vault_index.as_ref()) @ BlockDriveError::LegacyAccountLayout,
+        constraint = vault_index.load()?.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: AccountLoader<'info, UserVaultIndex>,
+}
+
+/// Rotate the master key commitment of a sharded vault
+#[derive(Accounts)]
+pub struct RotateMasterKeySharded<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Freeze a sharded vault (emergency)
+#[derive(Accounts)]
+pub struct FreezeVaultSharded<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Unfreeze a sharded vault
+#[derive(Accounts)]
+pub struct UnfreezeVaultSharded<'info> {
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Re-encrypt a sharded file under the master's current key epoch
+#[derive(Accounts)]
+pub struct ReencryptFileSharded<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = file_record.vault == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Report stale files of a sharded vault
+/// Read-only and signer-free so clients can simulate it
+#[derive(Accounts)]
+pub struct FindStaleFilesSharded<'info> {
+    #[account(
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+}
+
+/// Grow a Vault Master created before the status and commitment fields existed
+/// Such masters are too small to deserialize, so the account is taken unchecked.
+/// Anyone may pay for the resize, so a recovery is not blocked on the old owner
+#[derive(Accounts)]
+pub struct ResizeVaultMaster<'info> {
+    /// CHECK: Program ownership is enforced here; the discriminator is read from
+    /// the raw data in the handler
+    #[account(
+        mut,
+        owner = crate::ID @ BlockDriveError::Unauthorized
+    )]
+    pub vault_master: UncheckedAccount<'info>,
+
+    /// Pays the rent for the added bytes
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Convert a shard written with the earlier Borsh layout to the zero-copy one
+/// The account shrinks, so nobody pays; anyone may run it
+#[derive(Accounts)]
+pub struct MigrateShardLayout<'info> {
+    /// CHECK: Program ownership is enforced here; the discriminator and layout
+    /// are checked on the raw data in the handler
+    #[account(
+        mut,
+        owner = crate::ID @ BlockDriveError::Unauthorized
+    )]
+    pub vault_shard: UncheckedAccount<'info>,
+}
+
+/// Convert an index written with the earlier Borsh layout to the zero-copy one
+/// The account shrinks, so nobody pays; anyone may run it
+#[derive(Accounts)]
+pub struct MigrateIndexLayout<'info> {
+    /// CHECK: Program ownership is enforced here; the discriminator and layout
+    /// are checked on the raw data in the handler
+    #[account(
+        mut,
+        owner = crate::ID @ BlockDriveError::Unauthorized
+    )]
+    pub vault_index: UncheckedAccount<'info>,
+}
+
+// =============================================================================
+// INSTRUCTION HANDLERS
+// =============================================================================
+
+/// Initialize a new Vault Master with its accompanying index
+/// This is the first step when setting up sharded storage for a user
+pub fn initialize_vault_master(ctx: Context<InitializeVaultMaster>) -> Result<()> {
+    let clock = Clock::get()?;
+    let owner = ctx.accounts.owner.key();
+
+    require!(!ctx.accounts.config.is_paused, BlockDriveError::ProgramPaused);
+
+    // Initialize Vault Master
+    let vault_master = &mut ctx.accounts.vault_master;
+    vault_master.initialize(
+        ctx.bumps.vault_master,
+        owner,
+        clock.unix_timestamp,
+    );
+
+    // Allocate the Vault Index; extend_vault_index initializes it
+    let vault_master_key = vault_master.key();
+    allocate_index_page(
+        &ctx.accounts.owner,
+        &ctx.accounts.vault_index,
+        &ctx.accounts.system_program,
+        &[
+            UserVaultIndex::SEED_PREFIX,
+            vault_master_key.as_ref(),
+            &[ctx.bumps.vault_index],
+        ],
+        ctx.program_id,
+    )?;
+
+    emit!(VaultMasterCreated {
+        owner,
+        vault_master: vault_master.key(),
+        vault_index: ctx.accounts.vault_index.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Vault Master created for owner: {}", owner);
+    Ok(())
+}
+
+/// Create a new shard for storing files
+/// Shards are created incrementally as needed (when previous shard fills up)
+pub fn create_shard(ctx: Context<CreateShard>, shard_index: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault_master = &mut ctx.accounts.vault_master;
+    let vault_shard_key = ctx.accounts.vault_shard.key();
+    let mut vault_shard = ctx.accounts.vault_shard.load_init()?;
+    let owner = ctx.accounts.owner.key();
+
+    // Validate shard index matches expected next shard
+    require!(
+        shard_index == vault_master.total_shards,
+        BlockDriveError::InvalidShardIndex
+    );
+
+    // Check we haven't exceeded max shards
+    require!(
+        vault_master.can_create_shard(),
+        BlockDriveError::MaxShardsReached
+    );
+
+    // Initialize the new shard
+    vault_shard.initialize(
+        ctx.bumps.vault_shard,
+        vault_master.key(),
+        owner,
+        shard_index,
+        clock.unix_timestamp,
+    );
+
+    // Register shard in vault master (inline pointer for shards 0-9)
+    vault_master.register_shard(vault_shard_key, clock.unix_timestamp)?;
+
+    // Overflow shards are recorded in their directory page
+    if UserVaultMaster::page_for_shard(shard_index) > 0 {
+        let vault_directory = ctx
+            .accounts
+            .vault_directory
+            .as_mut()
+            .ok_or(BlockDriveError::DirectoryPageNotFound)?;
+        vault_directory.register_shard(shard_index, vault_shard_key, clock.unix_timestamp)?;
+    }
+
+    emit!(ShardCreated {
+        vault_master: vault_master.key(),
+        vault_shard: vault_shard_key,
+        shard_index,
+        owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Shard {} created for vault master", shard_index);
+    Ok(())
+}
+
+/// Add an overflow directory page with its index page
+/// Pages are created sequentially; page N covers shards N*10 .. N*10+9
+pub fn create_directory_page(ctx: Context<CreateDirectoryPage>, page_index: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault_master = &mut ctx.accounts.vault_master;
+    let vault_directory = &mut ctx.accounts.vault_directory;
+    let owner = ctx.accounts.owner.key();
+
+    // Validate page index matches expected next page
+    require!(
+        page_index == vault_master.directory_pages.saturating_add(1),
+        BlockDriveError::InvalidDirectoryPage
+    );
+
+    vault_master.register_directory_page(clock.unix_timestamp)?;
+
+    vault_directory.initialize(
+        ctx.bumps.vault_directory,
+        vault_master.key(),
+        owner,
+        page_index,
+        clock.unix_timestamp,
+    );
+
+    // Allocate the index page; extend_vault_index initializes it
+    let vault_master_key = vault_master.key();
+    allocate_index_page(
+        &ctx.accounts.owner,
+        &ctx.accounts.vault_index,
+        &ctx.accounts.system_program,
+        &[
+            UserVaultIndex::SEED_PREFIX,
+            vault_master_key.as_ref(),
+            &[page_index],
+            &[ctx.bumps.vault_index],
+        ],
+        ctx.program_id,
+    )?;
+
+    emit!(DirectoryPageCreated {
+        vault_master: vault_master.key(),
+        vault_directory: vault_directory.key(),
+        page_index,
+        owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    emit!(VaultIndexCreated {
+        vault_master: vault_master.key(),
+        vault_index: ctx.accounts.vault_index.key(),
+        owner,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Directory page {} created for vault master", page_index);
+    Ok(())
+}
+
+/// Grow an allocated index page to UserVaultIndex::MAX_SIZE and initialize it
+/// Pages exceed the 10 KiB a program may allocate, so the creating instruction
+/// only allocates part of the page (with rent for all of it) and this one adds
+/// the rest. No-op if the page is already initialized
+pub fn extend_vault_index(ctx: Context<ExtendVaultIndex>, page_index: u8) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault_master = &ctx.accounts.vault_master;
+    let vault_index = ctx.accounts.vault_index.to_account_info();
+
+    let (expected, bump) = if page_index == 0 {
+        derive_vault_index_pda(&vault_master.key(), ctx.program_id)
+    } else {
+        derive_vault_index_page_pda(&vault_master.key(), page_index, ctx.program_id)
+    };
+    require_keys_eq!(vault_index.key(), expected, BlockDriveError::Unauthorized);
+
+    if vault_index.data_len() >= UserVaultIndex::MAX_SIZE {
+        msg!("Vault index page {} already initialized", page_index);
+        return Ok(());
+    }
+
+    // Rent for the full size was paid when the page was allocated
+    vault_index.realloc(UserVaultIndex::MAX_SIZE, true)?;
+
+    let mut data = vault_index.try_borrow_mut_data()?;
+    data[..8].copy_from_slice(&UserVaultIndex::DISCRIMINATOR);
+    let index: &mut UserVaultIndex =
+        bytemuck::from_bytes_mut(&mut data[8..UserVaultIndex::MAX_SIZE]);
+    index.initialize(
+        bump,
+        vault_master.key(),
+        vault_master.owner,
+        page_index,
+        clock.unix_timestamp,
+    );
+
+    msg!("Vault index page {} initialized", page_index);
+    Ok(())
+}
+
+/// Register a new file to a specific shard
+/// This is the sharded version of register_file that works with the multi-PDA system
+pub fn register_file_sharded(
+    ctx: Context<RegisterFileSharded>,
+    file_id: [u8; 16],
+    shard_index: u8,
+    registration: FileRegistration,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &ctx.accounts.config;
+    let vault_master = &mut ctx.accounts.vault_master;
+    let mut vault_shard = ctx.accounts.vault_shard.load_mut()?;
+    let mut vault_index = ctx.accounts.vault_index.load_mut()?;
+    let file_record = &mut ctx.accounts.file_record;
+    let owner = ctx.accounts.owner.key();
+
+    require!(!config.is_paused, BlockDriveError::ProgramPaused);
+
+    // Enforce global limits
+    require!(config.is_file_size_allowed(registration.file_size), BlockDriveError::FileSizeExceeded);
+    require!(
+        config.can_add_file(vault_master.total_file_count),
+        BlockDriveError::VaultFileLimitReached
+    );
+
+    // Validate shard index
+    require!(
+        shard_index < vault_master.total_shards,
+        BlockDriveError::InvalidShardIndex
+    );
+
+    // Validate shard has capacity
+    require!(
+        vault_shard.has_capacity(),
+        BlockDriveError::ShardFull
+    );
+
+    // Validate the index page covers this shard
+    require!(
+        vault_index.covers_shard(shard_index),
+        BlockDriveError::InvalidDirectoryPage
+    );
+
+    // Initialize the file record
+    file_record.set_inner(FileRecord::new(
+        ctx.bumps.file_record,
+        vault_master.key(),
+        owner,
+        file_id,
+        &registration,
+        vault_master.key_epoch,
+        clock.unix_timestamp,
+    )?);
+
+    // Add file record to shard
+    let slot_index = vault_shard.add_file(file_record.key(), clock.unix_timestamp)?;
+
+    // Add entry to index for fast lookups
+    vault_index.add_entry(file_id, shard_index, slot_index, clock.unix_timestamp)?;
+
+    // Update master totals
+    vault_master.add_file(registration.file_size, clock.unix_timestamp);
+
+    collect_fee(
+        &ctx.accounts.owner,
+        &ctx.accounts.fee_collector.to_account_info(),
+        &ctx.accounts.system_program,
+        config.file_registration_fee,
+        FeeType::FileRegistration,
+        file_record.key(),
+        clock.unix_timestamp,
+    )?;
+
+    emit!(FileRegisteredSharded {
+        vault_master: vault_master.key(),
+        vault_shard: ctx.accounts.vault_shard.key(),
+        file_record: file_record.key(),
+        file_id,
+        shard_index,
+        slot_index,
+        file_size: registration.file_size,
+        encrypted_size: registration.encrypted_size,
+        security_level: registration.security_level,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "File registered to shard {} slot {} (total files: {})",
+        shard_index,
+        slot_index,
+        vault_master.total_file_count
+    );
+
+    Ok(())
+}
+
+/// Register a new file without choosing a shard
+/// Targets the active shard; when it is full, advances to the next shard,
+/// creating it in the same transaction if needed
+pub fn register_file_auto(
+    ctx: Context<RegisterFileAuto>,
+    file_id: [u8; 16],
+    registration: FileRegistration,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let config = &ctx.accounts.config;
+    let vault_master = &mut ctx.accounts.vault_master;
+    let owner = ctx.accounts.owner.key();
+
+    require!(!config.is_paused, BlockDriveError::ProgramPaused);
+
+    // Enforce global limits
+    require!(config.is_file_size_allowed(registration.file_size), BlockDriveError::FileSizeExceeded);
+    require!(
+        config.can_add_file(vault_master.total_file_count),
+        BlockDriveError::VaultFileLimitReached
+    );
+
+    // Use the active shard while it has room
+    let active_has_capacity = match &ctx.accounts.active_shard {
+        Some(active_shard) => active_shard.load()?.has_capacity(),
+        None => {
+            require!(
+                vault_master.total_shards == 0 || vault_master.all_shards_closed(),
+                BlockDriveError::ShardNotFound
+            );
+            false
+        }
+    };
+
+    let (shard_info, shard_index) = if active_has_capacity {
+        let active_shard = ctx.accounts.active_shard.as_ref().unwrap();
+        (active_shard.to_account_info(), vault_master.active_shard_index)
+    } else {
+        let next_shard = ctx
+            .accounts
+            .next_shard
+            .as_ref()
+            .ok_or(BlockDriveError::NoAvailableShard)?;
+        let next_shard_info = next_shard.to_account_info();
+
+        let shard_index = if !next_shard_info.data_is_empty() {
+            // Shard was already created ahead of time; skip past any full or
+            // closed shards and move onto it
+            let shard_index = {
+                let vault_shard = borrow_shard_mut(&next_shard_info, ctx.program_id)?;
+                require_keys_eq!(
+                    vault_shard.vault_master,
+                    vault_master.key(),
+                    BlockDriveError::Unauthorized
+                );
+                require!(vault_shard.has_capacity(), BlockDriveError::ShardFull);
+                vault_shard.shard_index
+            };
+            vault_master.activate_shard(shard_index, clock.unix_timestamp)?;
+            shard_index
+        } else {
+            require!(
+                vault_master.can_create_shard(),
+                BlockDriveError::MaxShardsReached
+            );
+
+            let shard_index = vault_master.total_shards;
+            let (expected, bump) = Pubkey::find_program_address(
+                &[
+                    UserVaultShard::SEED_PREFIX,
+                    vault_master.key().as_ref(),
+                    &[shard_index],
+                ],
+                ctx.program_id,
+            );
+            require_keys_eq!(
+                next_shard_info.key(),
+                expected,
+                BlockDriveError::InvalidShardIndex
+            );
+            create_shard_account(
+                &ctx.accounts.payer,
+                &next_shard_info,
+                &ctx.accounts.system_program,
+                &vault_master.key(),
+                shard_index,
+                bump,
+                ctx.program_id,
+            )?;
+            borrow_shard_mut(&next_shard_info, ctx.program_id)?.initialize(
+                bump,
+                vault_master.key(),
+                owner,
+                shard_index,
+                clock.unix_timestamp,
+            );
+
+            // Register shard in vault master (also makes it the active shard)
+            vault_master.register_shard(next_shard_info.key(), clock.unix_timestamp)?;
+
+            // Overflow shards are recorded in their directory page
+            if UserVaultMaster::page_for_shard(shard_index) > 0 {
+                let vault_directory = ctx
+                    .accounts
+                    .vault_directory
+                    .as_mut()
+                    .ok_or(BlockDriveError::DirectoryPageNotFound)?;
+                vault_directory.register_shard(
+                    shard_index,
+                    next_shard_info.key(),
+                    clock.unix_timestamp,
+                )?;
+            }
+
+            emit!(ShardCreated {
+                vault_master: vault_master.key(),
+                vault_shard: next_shard_info.key(),
+                shard_index,
+                owner,
+                timestamp: clock.unix_timestamp,
+            });
+
+            msg!("Shard {} created for vault master", shard_index);
+            shard_index
+        };
+
+        (next_shard_info, shard_index)
+    };
+
+    let mut vault_shard = borrow_shard_mut(&shard_info, ctx.program_id)?;
+    require!(
+        vault_shard.shard_index == shard_index,
+        BlockDriveError::InvalidShardIndex
+    );
+
+    // Pick whichever index page covers the chosen shard
+    let vault_index_loader = if ctx.accounts.vault_index.load()?.covers_shard(shard_index) {
+        &ctx.accounts.vault_index
+    } else {
+        ctx.accounts
+            .next_vault_index
+            .as_ref()
+            .ok_or(BlockDriveError::InvalidDirectoryPage)?
+    };
+    let mut vault_index = vault_index_loader.load_mut()?;
+    require!(
+        vault_index.covers_shard(shard_index),
+        BlockDriveError::InvalidDirectoryPage
+    );
+
+    // Initialize the file record
+    let file_record = &mut ctx.accounts.file_record;
+    file_record.set_inner(FileRecord::new(
+        ctx.bumps.file_record,
+        vault_master.key(),
+        owner,
+        file_id,
+        &registration,
+        vault_master.key_epoch,
+        clock.unix_timestamp,
+    )?);
+
+    // Add file record to shard and index
+    let slot_index = vault_shard.add_file(file_record.key(), clock.unix_timestamp)?;
+    vault_index.add_entry(file_id, shard_index, slot_index, clock.unix_timestamp)?;
+
+    // Update master totals
+    vault_master.add_file(registration.file_size, clock.unix_timestamp);
+
+    collect_fee(
+        &ctx.accounts.owner,
+        &ctx.accounts.fee_collector.to_account_info(),
+        &ctx.accounts.system_program,
+        config.file_registration_fee,
+        FeeType::FileRegistration,
+        file_record.key(),
+        clock.unix_timestamp,
+    )?;
+
+    emit!(FileRegisteredSharded {
+        vault_master: vault_master.key(),
+        vault_shard: shard_info.key(),
+        file_record: file_record.key(),
+        file_id,
+        shard_index,
+        slot_index,
+        file_size: registration.file_size,
+        encrypted_size: registration.encrypted_size,
+        security_level: registration.security_level,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "File auto-registered to shard {} slot {} (total files: {})",
+        shard_index,
+        slot_index,
+        vault_master.total_file_count
+    );
+
+    Ok(())
+}
+
+/// Delete a file from a sharded vault
+/// Clears the shard slot, removes the index entry, updates master totals
+/// and closes the FileRecord (rent refunded to owner)
+pub fn delete_file_sharded<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DeleteFileSharded<'info>>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
+    revoke_all_delegations(
+        &mut ctx.accounts.file_record,
+        ctx.remaining_accounts,
+        clock.unix_timestamp,
+    )?;
+
+    let vault_master = &mut ctx.accounts.vault_master;
+    let mut vault_shard = ctx.accounts.vault_shard.load_mut()?;
+    let mut vault_index = ctx.accounts.vault_index.load_mut()?;
+    let file_record = &ctx.accounts.file_record;
+    let file_id = file_record.file_id;
+
+    // Versions are seeded off the record and would be orphaned
+    require!(
+        file_record.retained_versions == 0,
+        BlockDriveError::FileHasVersions
+    );
+
+    // Resolve the file's location from the index
+    let (shard_index, slot_index) = vault_index
+        .find_entry(&file_id)
+        .ok_or(BlockDriveError::FileNotFound)?;
+
+    require!(
+        vault_shard.shard_index == shard_index,
+        BlockDriveError::ShardNotFound
+    );
+    require!(
+        vault_shard.get_file(slot_index) == Some(file_record.key()),
+        BlockDriveError::FileNotFound
+    );
+
+    vault_shard.remove_file(slot_index, clock.unix_timestamp)?;
+    vault_index.remove_entry(&file_id, clock.unix_timestamp)?;
+    vault_master.remove_file(file_record.file_size, clock.unix_timestamp);
+
+    emit!(FileDeletedSharded {
+        vault_master: vault_master.key(),
+        vault_shard: ctx.accounts.vault_shard.key(),
+        file_record: file_record.key(),
+        file_id,
+        shard_index,
+        slot_index,
+        file_size: file_record.file_size,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "File deleted from shard {} slot {} (total files: {})",
+        shard_index,
+        slot_index,
+        vault_master.total_file_count
+    );
+
+    // Account is closed automatically via `close = owner` constraint
+    Ok(())
+}
+
+/// Compact a shard so its files occupy slots 0..file_count
+/// Every moved slot is written back to the index with update_entry
+pub fn compact_shard(ctx: Context<CompactShard>) -> Result<()> {
+    let clock = Clock::get()?;
+    let mut vault_shard = ctx.accounts.vault_shard.load_mut()?;
+    let mut vault_index = ctx.accounts.vault_index.load_mut()?;
+    let shard_index = vault_shard.shard_index;
+
+    require!(
+        vault_index.covers_shard(shard_index),
+        BlockDriveError::InvalidDirectoryPage
+    );
+
+    // Snapshot the shard's index entries before slots change
+    let entries: Vec<([u8; 16], u8)> = vault_index
+        .get_entries_for_shard(shard_index)
+        .iter()
+        .map(|e| (e.file_id, e.slot_index))
+        .collect();
+
+    let moves = vault_shard.compact(clock.unix_timestamp);
+    for (from_slot, to_slot) in moves.iter() {
+        let (file_id, _) = entries
+            .iter()
+            .find(|(_, slot)| slot == from_slot)
+            .ok_or(BlockDriveError::FileNotFound)?;
+        vault_index.update_entry(file_id, shard_index, *to_slot, clock.unix_timestamp)?;
+    }
+
+    emit!(ShardCompacted {
+        vault_master: ctx.accounts.vault_master.key(),
+        vault_shard: ctx.accounts.vault_shard.key(),
+        shard_index,
+        files_moved: moves.len() as u8,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Shard {} compacted ({} files moved)", shard_index, moves.len());
+    Ok(())
+}
+
+/// Move a file record into another shard
+/// The file takes the target shard's first free slot; the index entry is
+/// updated in place, or moved across pages when the shards are on different pages
+pub fn rebalance_file(ctx: Context<RebalanceFile>) -> Result<()> {
+    let clock = Clock::get()?;
+    let file_record = &ctx.accounts.file_record;
+    let file_id = file_record.file_id;
+    let mut source_shard = ctx.accounts.source_shard.load_mut()?;
+    let mut target_shard = ctx.accounts.target_shard.load_mut()?;
+    let mut source_index = ctx.accounts.source_index.load_mut()?;
+
+    // Resolve the file's current location from the index
+    let (from_shard_index, from_slot_index) = source_index
+        .find_entry(&file_id)
+        .ok_or(BlockDriveError::FileNotFound)?;
+
+    require!(
+        source_shard.shard_index == from_shard_index,
+        BlockDriveError::ShardNotFound
+    );
+    require!(
+        source_shard.get_file(from_slot_index) == Some(file_record.key()),
+        BlockDriveError::FileNotFound
+    );
+
+    let to_shard_index = target_shard.shard_index;
+    let to_slot_index = target_shard.add_file(file_record.key(), clock.unix_timestamp)?;
+    source_shard.remove_file(from_slot_index, clock.unix_timestamp)?;
+
+    if source_index.covers_shard(to_shard_index) {
+        source_index.update_entry(&file_id, to_shard_index, to_slot_index, clock.unix_timestamp)?;
+    } else {
+        let mut target_index = ctx
+            .accounts
+            .target_index
+            .as_ref()
+            .ok_or(BlockDriveError::InvalidDirectoryPage)?
+            .load_mut()?;
+        require!(
+            target_index.covers_shard(to_shard_index),
+            BlockDriveError::InvalidDirectoryPage
+        );
+        source_index.remove_entry(&file_id, clock.unix_timestamp)?;
+        target_index.add_entry(file_id, to_shard_index, to_slot_index, clock.unix_timestamp)?;
+    }
+
+    emit!(FileRebalanced {
+        vault_master: ctx.accounts.vault_master.key(),
+        file_record: file_record.key(),
+        file_id,
+        from_shard_index,
+        from_slot_index,
+        to_shard_index,
+        to_slot_index,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "File moved from shard {} slot {} to shard {} slot {}",
+        from_shard_index,
+        from_slot_index,
+        to_shard_index,
+        to_slot_index
+    );
+    Ok(())
+}
+
+/// Update a sharded file's storage with redundancy info
+pub fn update_file_storage_sharded(
+    ctx: Context<UpdateFileStorageSharded>,
+    redundancy_cid: [u8; 64],
+    provider_count: u8,
+) -> Result<()> {
+    let file_record = &mut ctx.accounts.file_record;
+    let clock = Clock::get()?;
+
+    require!(file_record.is_active(), BlockDriveError::FileNotActive);
+
+    file_record.redundancy_cid = redundancy_cid;
+    file_record.provider_count = provider_count;
+
+    emit!(FileStorageUpdated {
+        file_record: file_record.key(),
+        provider_count,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Archive a sharded file
+/// The file keeps its shard slot and index entry
+pub fn archive_file_sharded<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ArchiveFileSharded<'info>>,
+) -> Result<()> {
+    let file_record = &mut ctx.accounts.file_record;
+    let clock = Clock::get()?;
+
+    require!(file_record.is_active(), BlockDriveError::FileNotActive);
+
+    revoke_all_delegations(file_record, ctx.remaining_accounts, clock.unix_timestamp)?;
+
+    file_record.status = FileStatus::Archived;
+
+    emit!(FileArchived {
+        vault: ctx.accounts.vault_master.key(),
+        file_record: file_record.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Restore an archived sharded file to active
+pub fn unarchive_file_sharded(ctx: Context<UnarchiveFileSharded>) -> Result<()> {
+    let file_record = &mut ctx.accounts.file_record;
+    let clock = Clock::get()?;
+
+    require!(file_record.is_archived(), BlockDriveError::FileNotArchived);
+
+    file_record.status = FileStatus::Active;
+
+    emit!(FileUnarchived {
+        vault: ctx.accounts.vault_master.key(),
+        file_record: file_record.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Archive a shard (read-only)
+/// Archived shards keep their files but no longer accept new ones
+pub fn archive_shard(ctx: Context<ArchiveShard>) -> Result<()> {
+    let clock = Clock::get()?;
+    let mut vault_shard = ctx.accounts.vault_shard.load_mut()?;
+
+    require!(
+        vault_shard.status() != ShardStatus::Archived,
+        BlockDriveError::ShardArchived
+    );
+
+    vault_shard.archive(clock.unix_timestamp);
+
+    emit!(ShardArchived {
+        vault_master: ctx.accounts.vault_master.key(),
+        vault_shard: ctx.accounts.vault_shard.key(),
+        shard_index: vault_shard.shard_index,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Shard {} archived", vault_shard.shard_index);
+    Ok(())
+}
+
+/// Close an empty shard, returning its rent to the owner
+pub fn close_shard(ctx: Context<CloseShard>) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault_master = &mut ctx.accounts.vault_master;
+    let vault_shard_key = ctx.accounts.vault_shard.key();
+
+    upgrade_shard_layout(ctx.accounts.vault_shard.as_ref())?;
+    let shard_index = {
+        let vault_shard = ctx.accounts.vault_shard.load()?;
+        require_keys_eq!(
+            vault_shard.vault_master,
+            vault_master.key(),
+            BlockDriveError::Unauthorized
+        );
+        require!(vault_shard.file_count == 0, BlockDriveError::ShardNotEmpty);
+        vault_shard.shard_index
+    };
+
+    vault_master.close_shard(shard_index, clock.unix_timestamp)?;
+
+    // Overflow shards are also cleared from their directory page
+    if UserVaultMaster::page_for_shard(shard_index) > 0 {
+        let vault_directory = ctx
+            .accounts
+            .vault_directory
+            .as_mut()
+            .ok_or(BlockDriveError::DirectoryPageNotFound)?;
+        vault_directory.unregister_shard(shard_index, clock.unix_timestamp)?;
+    }
+
+    emit!(ShardClosed {
+        vault_master: vault_master.key(),
+        vault_shard: vault_shard_key,
+        shard_index,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Shard {} closed", shard_index);
 
-    pub system_program: Program<'info, System>,
+    // Account is closed automatically via `close = owner` constraint
+    Ok(())
 }
 
-/// Register a file to a specific shard (sharded version of register_file)
-#[derive(Accounts)]
-#[instruction(file_id: [u8; 16], shard_index: u8)]
-pub struct RegisterFileSharded<'info> {
-    #[account(
-        mut,
-        seeds = [UserVaultMaster::SEED_PREFIX, owner.key().as_ref()],
-        bump = vault_master.bump,
-        has_one = owner @ BlockDriveError::Unauthorized
-    )]
-    pub vault_master: Account<'info, UserVaultMaster>,
+/// Tear down an empty sharded vault
+/// Requires no files and every shard closed; the master, base index and all
+/// overflow directory/index pages are closed with rent returned to the owner
+pub fn close_vault_master<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseVaultMaster<'info>>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault_master = &ctx.accounts.vault_master;
+    let vault_master_key = vault_master.key();
+    let owner = ctx.accounts.owner.to_account_info();
 
-    #[account(
-        mut,
-        seeds = [
-            UserVaultShard::SEED_PREFIX,
-            vault_master.key().as_ref(),
-            &[shard_index]
-        ],
-        bump = vault_shard.bump,
-        constraint = vault_shard.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
-    )]
-    pub vault_shard: Account<'info, UserVaultShard>,
+    require!(vault_master.total_file_count == 0, BlockDriveError::VaultNotEmpty);
+    require!(vault_master.all_shards_closed(), BlockDriveError::ShardsStillOpen);
 
-    #[account(
-        mut,
-        seeds = [UserVaultIndex::SEED_PREFIX, vault_master.key().as_ref()],
-        bump = vault_index.bump,
-        constraint = vault_index.vault_master == vault_master.key() @ BlockDriveError::Unauthorized
-    )]
-    pub vault_index: Account<'info, UserVaultIndex>,
+    // Every overflow page must be closed with the master
+    let directory_pages = vault_master.directory_pages;
+    require!(
+        ctx.remaining_accounts.len() == directory_pages as usize * 2,
+        BlockDriveError::MissingDirectoryPages
+    );
 
-    #[account(
-        init,
-        payer = owner,
-        space = FileRecord::SIZE,
-        seeds = [FileRecord::SEED_PREFIX, vault_master.key().as_ref(), &file_id],
-        bump
-    )]
-    pub file_record: Account<'info, FileRecord>,
+    for (page, accounts) in ctx.remaining_accounts.chunks(2).enumerate() {
+        let page_index = page as u8 + 1;
 
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        let vault_directory = Account::<UserVaultDirectory>::try_from(&accounts[0])?;
+        require!(
+            vault_directory.vault_master == vault_master_key
+                && vault_directory.page_index == page_index,
+            BlockDriveError::InvalidDirectoryPage
+        );
 
-    pub system_program: Program<'info, System>,
-}
+        upgrade_index_layout(&accounts[1])?;
+        let vault_index = AccountLoader::<UserVaultIndex>::try_from(&accounts[1])?;
+        {
+            let index = vault_index.load()?;
+            require!(
+                index.vault_master == vault_master_key && index.page_index == page_index,
+                BlockDriveError::InvalidDirectoryPage
+            );
+        }
 
-/// Query the vault index to find a file's location
-#[derive(Accounts)]
-pub struct QueryFileLocation<'info> {
-    #[account(
-        seeds = [UserVaultMaster::SEED_PREFIX, owner.key().as_ref()],
-        bump = vault_master.bump,
-    )]
-    pub vault_master: Account<'info, UserVaultMaster>,
+        vault_directory.close(owner.clone())?;
+        vault_index.close(owner.clone())?;
+    }
 
-    #[account(
-        seeds = [UserVaultIndex::SEED_PREFIX, vault_master.key().as_ref()],
-        bump = vault_index.bump,
-    )]
-    pub vault_index: Account<'info, UserVaultIndex>,
+    emit!(VaultMasterClosed {
+        owner: owner.key(),
+        vault_master: vault_master_key,
+        directory_pages,
+        timestamp: clock.unix_timestamp,
+    });
 
-    pub owner: Signer<'info>,
+    msg!("Vault master closed ({} overflow pages)", directory_pages);
+
+    // Master and base index are closed automatically via `close = owner` constraints
+    Ok(())
 }
 
-// =============================================================================
-// INSTRUCTION HANDLERS
-// =============================================================================
+/// Query the vault index to find which shard contains a file
+/// Returns the shard, slot and FileRecord pubkey or error if not found
+/// Note: This is a read-only instruction; the result is passed back as return data
+pub fn query_file_location(
+    ctx: Context<QueryFileLocation>,
+    file_id: [u8; 16],
+) -> Result<FileLocation> {
+    let vault_index = ctx.accounts.vault_index.load()?;
 
-/// Initialize a new Vault Master with its accompanying index
-/// This is the first step when setting up sharded storage for a user
-pub fn initialize_vault_master(ctx: Context<InitializeVaultMaster>) -> Result<()> {
-    let clock = Clock::get()?;
-    let owner = ctx.accounts.owner.key();
+    let (shard_index, slot_index) = vault_index
+        .find_entry(&file_id)
+        .ok_or(BlockDriveError::FileNotFound)?;
 
-    // Initialize Vault Master
-    let vault_master = &mut ctx.accounts.vault_master;
-    vault_master.initialize(
-        ctx.bumps.vault_master,
-        owner,
-        clock.unix_timestamp,
+    let (file_record, _) = derive_file_record_pda(
+        &ctx.accounts.vault_master.key(),
+        &file_id,
+        ctx.program_id,
     );
 
-    // Initialize Vault Index
-    let vault_index = &mut ctx.accounts.vault_index;
-    vault_index.initialize(
-        ctx.bumps.vault_index,
-        vault_master.key(),
-        owner,
-        clock.unix_timestamp,
-    );
+    msg!("File found at shard {} slot {}", shard_index, slot_index);
 
-    emit!(VaultMasterCreated {
-        owner,
-        vault_master: vault_master.key(),
-        vault_index: vault_index.key(),
+    Ok(FileLocation {
+        shard_index,
+        slot_index,
+        file_record,
+    })
+}
+
+/// Rotate the master key commitment of a sharded vault
+/// The first call records the commitment for masters created without one
+pub fn rotate_master_key_sharded(
+    ctx: Context<RotateMasterKeySharded>,
+    new_commitment: [u8; 32],
+) -> Result<()> {
+    let vault_master = &mut ctx.accounts.vault_master;
+    let clock = Clock::get()?;
+
+    require!(vault_master.is_active(), BlockDriveError::VaultNotActive);
+
+    vault_master.rotate_master_key(new_commitment, clock.unix_timestamp);
+
+    emit!(MasterKeyRotated {
+        vault: vault_master.key(),
+        key_epoch: vault_master.key_epoch,
         timestamp: clock.unix_timestamp,
     });
 
-    msg!("Vault Master created for owner: {}", owner);
     Ok(())
 }
 
-/// Create a new shard for storing files
-/// Shards are created incrementally as needed (when previous shard fills up)
-pub fn create_shard(ctx: Context<CreateShard>, shard_index: u8) -> Result<()> {
-    let clock = Clock::get()?;
+/// Freeze a sharded vault (emergency)
+pub fn freeze_vault_sharded(ctx: Context<FreezeVaultSharded>) -> Result<()> {
     let vault_master = &mut ctx.accounts.vault_master;
-    let vault_shard = &mut ctx.accounts.vault_shard;
-    let owner = ctx.accounts.owner.key();
+    let clock = Clock::get()?;
 
-    // Validate shard index matches expected next shard
-    require!(
-        shard_index == vault_master.total_shards,
-        BlockDriveError::InvalidShardIndex
-    );
+    require!(vault_master.is_active(), BlockDriveError::VaultNotActive);
 
-    // Check we haven't exceeded max shards
-    require!(
-        vault_master.can_create_shard(),
-        BlockDriveError::MaxShardsReached
-    );
+    vault_master.freeze(clock.unix_timestamp);
 
-    // Initialize the new shard
-    vault_shard.initialize(
-        ctx.bumps.vault_shard,
-        vault_master.key(),
-        owner,
-        shard_index,
-        clock.unix_timestamp,
+    emit!(VaultFrozen {
+        vault: vault_master.key(),
+        frozen_by: ctx.accounts.owner.key(),
+        reason: FreezeReason::OwnerRequest,
+        unfreeze_after: vault_master.unfreeze_after(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Unfreeze a sharded vault once the unfreeze timelock has elapsed
+pub fn unfreeze_vault_sharded(ctx: Context<UnfreezeVaultSharded>) -> Result<()> {
+    let vault_master = &mut ctx.accounts.vault_master;
+    let clock = Clock::get()?;
+
+    require!(vault_master.is_frozen(), BlockDriveError::VaultNotActive);
+    require!(
+        vault_master.can_unfreeze(clock.unix_timestamp),
+        BlockDriveError::UnfreezeTimelockActive
     );
 
-    // Register shard in vault master
-    vault_master.register_shard(vault_shard.key(), clock.unix_timestamp)?;
+    vault_master.status = VaultStatus::Active;
+    vault_master.updated_at = clock.unix_timestamp;
 
-    emit!(ShardCreated {
-        vault_master: vault_master.key(),
-        vault_shard: vault_shard.key(),
-        shard_index,
-        owner,
+    emit!(VaultUnfrozen {
+        vault: vault_master.key(),
         timestamp: clock.unix_timestamp,
     });
 
-    msg!("Shard {} created for vault master", shard_index);
     Ok(())
 }
 
-/// Register a new file to a specific shard
-/// This is the sharded version of register_file that works with the multi-PDA system
-pub fn register_file_sharded(
-    ctx: Context<RegisterFileSharded>,
-    file_id: [u8; 16],
-    shard_index: u8,
-    filename_hash: [u8; 32],
-    file_size: u64,
-    encrypted_size: u64,
-    mime_type_hash: [u8; 32],
-    security_level: u8,
+/// Re-encrypt a sharded file under the master's current key epoch
+pub fn reencrypt_file_sharded(
+    ctx: Context<ReencryptFileSharded>,
     encryption_commitment: [u8; 32],
     critical_bytes_commitment: [u8; 32],
     primary_cid: [u8; 64],
+    redundancy_cid: [u8; 64],
 ) -> Result<()> {
-    let clock = Clock::get()?;
-    let vault_master = &mut ctx.accounts.vault_master;
-    let vault_shard = &mut ctx.accounts.vault_shard;
-    let vault_index = &mut ctx.accounts.vault_index;
+    let vault_master = &ctx.accounts.vault_master;
     let file_record = &mut ctx.accounts.file_record;
-    let owner = ctx.accounts.owner.key();
+    let clock = Clock::get()?;
 
-    // Validate shard index
     require!(
-        shard_index < vault_master.total_shards,
-        BlockDriveError::InvalidShardIndex
+        file_record.is_active() || file_record.is_archived(),
+        BlockDriveError::FileNotActive
     );
-
-    // Validate shard has capacity
     require!(
-        vault_shard.has_capacity(),
-        BlockDriveError::ShardFull
+        file_record.needs_reencryption(vault_master.key_epoch),
+        BlockDriveError::FileKeyEpochCurrent
     );
 
-    // Validate security level
-    let sec_level = SecurityLevel::from_u8(security_level)
-        .ok_or(BlockDriveError::InvalidSecurityLevel)?;
+    let previous_key_epoch = file_record.key_epoch;
 
-    // Initialize the file record
-    file_record.bump = ctx.bumps.file_record;
-    file_record.vault = vault_master.key(); // Points to master now
-    file_record.owner = owner;
-    file_record.file_id = file_id;
-    file_record.filename_hash = filename_hash;
-    file_record.file_size = file_size;
-    file_record.encrypted_size = encrypted_size;
-    file_record.mime_type_hash = mime_type_hash;
-    file_record.security_level = sec_level;
     file_record.encryption_commitment = encryption_commitment;
     file_record.critical_bytes_commitment = critical_bytes_commitment;
     file_record.primary_cid = primary_cid;
-    file_record.redundancy_cid = [0u8; 64];
-    file_record.provider_count = 1;
-    file_record.created_at = clock.unix_timestamp;
-    file_record.accessed_at = clock.unix_timestamp;
-    file_record.status = FileStatus::Active;
-    file_record.is_shared = false;
-    file_record.delegation_count = 0;
-    file_record.reserved = [0u8; 32];
-
-    // Add file record to shard
-    let slot_index = vault_shard.add_file(file_record.key(), clock.unix_timestamp)?;
-
-    // Add entry to index for fast lookups
-    vault_index.add_entry(file_id, shard_index, slot_index, clock.unix_timestamp)?;
-
-    // Update master totals
-    vault_master.add_file(file_size, clock.unix_timestamp);
+    file_record.redundancy_cid = redundancy_cid;
+    file_record.key_epoch = vault_master.key_epoch;
 
-    emit!(FileRegisteredSharded {
-        vault_master: vault_master.key(),
-        vault_shard: vault_shard.key(),
+    emit!(FileReencrypted {
+        vault: vault_master.key(),
         file_record: file_record.key(),
-        file_id,
-        shard_index,
-        slot_index,
-        file_size,
-        encrypted_size,
-        security_level,
+        previous_key_epoch,
+        key_epoch: file_record.key_epoch,
         timestamp: clock.unix_timestamp,
     });
 
-    msg!(
-        "File registered to shard {} slot {} (total files: {})",
-        shard_index,
-        slot_index,
-        vault_master.total_file_count
-    );
-
     Ok(())
 }
 
-/// Query the vault index to find which shard contains a file
-/// Returns (shard_index, slot_index) or error if not found
-/// Note: This is a read-only instruction for client convenience
-pub fn query_file_location(
-    ctx: Context<QueryFileLocation>,
-    file_id: [u8; 16],
-) -> Result<(u8, u8)> {
-    let vault_index = &ctx.accounts.vault_index;
+/// Report which of the given FileRecords lag behind the master's key epoch
+/// FileRecords are passed as remaining accounts; records of other vaults are ignored
+pub fn find_stale_files_sharded<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FindStaleFilesSharded<'info>>,
+) -> Result<Vec<Pubkey>> {
+    let vault_master = &ctx.accounts.vault_master;
+    collect_stale_files(vault_master.key(), vault_master.key_epoch, ctx.remaining_accounts)
+}
 
-    let location = vault_index
-        .find_entry(&file_id)
-        .ok_or(BlockDriveError::FileNotFound)?;
+/// Grow a pre-existing Vault Master to the current layout
+/// The owner tops up rent; the appended bytes are zeroed, which decodes as an
+/// active vault at key epoch 0 with no commitment. No-op if already resized
+pub fn resize_vault_master(ctx: Context<ResizeVaultMaster>) -> Result<()> {
+    let vault_master = ctx.accounts.vault_master.to_account_info();
+
+    {
+        let data = vault_master.try_borrow_data()?;
+        require!(
+            data.len() >= UserVaultMaster::LEGACY_SIZE
+                && data[..8] == UserVaultMaster::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        if data.len() >= UserVaultMaster::SIZE {
+            msg!("Vault Master already at current size");
+            return Ok(());
+        }
+    }
+
+    let required = Rent::get()?.minimum_balance(UserVaultMaster::SIZE);
+    let shortfall = required.saturating_sub(vault_master.lamports());
+    if shortfall > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.payer.to_account_info(),
+            to: vault_master.clone(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, shortfall)?;
+    }
+
+    vault_master.realloc(UserVaultMaster::SIZE, true)?;
+
+    msg!("Vault Master resized to {} bytes", UserVaultMaster::SIZE);
+    Ok(())
+}
 
-    msg!("File {} found at shard {} slot {}", 
-        hex::encode(file_id), location.0, location.1);
+/// Rewrite a shard from the earlier Borsh layout and shrink it to the zero-copy
+/// size. The freed rent stays in the account until it is closed. No-op if the
+/// shard is already converted
+pub fn migrate_shard_layout(ctx: Context<MigrateShardLayout>) -> Result<()> {
+    if upgrade_shard_layout(ctx.accounts.vault_shard.as_ref())? {
+        msg!("Shard converted to {} bytes", UserVaultShard::SIZE);
+    } else {
+        msg!("Shard already uses the current layout");
+    }
+    Ok(())
+}
 
-    Ok(location)
+/// Rewrite an index page from the earlier Borsh layout with sorted entries and
+/// shrink it to the zero-copy size. The freed rent stays in the account
+/// until it is closed. No-op if the index is already converted
+pub fn migrate_index_layout(ctx: Context<MigrateIndexLayout>) -> Result<()> {
+    if upgrade_index_layout(ctx.accounts.vault_index.as_ref())? {
+        msg!("Index converted to {} bytes", UserVaultIndex::MAX_SIZE);
+    } else {
+        msg!("Index already uses the current layout");
+    }
+    Ok(())
 }
 
 // =============================================================================
@@ -358,7 +1804,7 @@ pub fn derive_vault_shard_pda(
     )
 }
 
-/// Derive the Vault Index PDA address
+/// Derive the Vault Index PDA address (page 0)
 pub fn derive_vault_index_pda(vault_master: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
         &[UserVaultIndex::SEED_PREFIX, vault_master.as_ref()],
@@ -366,6 +1812,38 @@ pub fn derive_vault_index_pda(vault_master: &Pubkey, program_id: &Pubkey) -> (Pu
     )
 }
 
+/// Derive a Vault Index page PDA address (page 1+)
+pub fn derive_vault_index_page_pda(
+    vault_master: &Pubkey,
+    page_index: u8,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            UserVaultIndex::SEED_PREFIX,
+            vault_master.as_ref(),
+            &[page_index],
+        ],
+        program_id,
+    )
+}
+
+/// Derive a Vault Directory page PDA address (page 1+)
+pub fn derive_vault_directory_pda(
+    vault_master: &Pubkey,
+    page_index: u8,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            UserVaultDirectory::SEED_PREFIX,
+            vault_master.as_ref(),
+            &[page_index],
+        ],
+        program_id,
+    )
+}
+
 /// Derive a FileRecord PDA address (sharded version)
 pub fn derive_file_record_pda(
     vault_master: &Pubkey,
@@ -377,3 +1855,178 @@ pub fn derive_file_record_pda(
         program_id,
     )
 }
+
+/// Create a Vault Shard PDA account owned by this program, funded by payer
+/// Used when a shard is created lazily inside another instruction
+fn create_shard_account<'info>(
+    payer: &Signer<'info>,
+    shard_info: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    vault_master: &Pubkey,
+    shard_index: u8,
+    bump: u8,
+    program_id: &Pubkey,
+) -> Result<()> {
+    create_pda_account(
+        payer,
+        shard_info,
+        system_program,
+        UserVaultShard::SIZE,
+        Rent::get()?.minimum_balance(UserVaultShard::SIZE),
+        &[
+            UserVaultShard::SEED_PREFIX,
+            vault_master.as_ref(),
+            &[shard_index],
+            &[bump],
+        ],
+        program_id,
+    )?;
+
+    // Stamp the discriminator so the account loads like any other shard
+    shard_info.try_borrow_mut_data()?[..8].copy_from_slice(&UserVaultShard::DISCRIMINATOR);
+    Ok(())
+}
+
+/// Allocate an index page PDA as far as a program may in one instruction
+/// Rent covers the full UserVaultIndex::MAX_SIZE; extend_vault_index grows the
+/// page the rest of the way and initializes it, so clients send both together
+pub(crate) fn allocate_index_page<'info>(
+    payer: &Signer<'info>,
+    index_info: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<()> {
+    create_pda_account(
+        payer,
+        index_info,
+        system_program,
+        MAX_PERMITTED_DATA_INCREASE.min(UserVaultIndex::MAX_SIZE),
+        Rent::get()?.minimum_balance(UserVaultIndex::MAX_SIZE),
+        seeds,
+        program_id,
+    )
+}
+
+/// Create a PDA account of `space` bytes owned by this program, funded by payer
+/// Like Anchor's `init`, an address that was pre-funded (e.g. by a stray
+/// transfer) is topped up, allocated and assigned instead of failing create_account
+pub(crate) fn create_pda_account<'info>(
+    payer: &Signer<'info>,
+    account_info: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    lamports: u64,
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let current_lamports = account_info.lamports();
+    if current_lamports == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account_info.clone(),
+                },
+                signer_seeds,
+            ),
+            lamports,
+            space as u64,
+            program_id,
+        )?;
+    } else {
+        let shortfall = lamports.saturating_sub(current_lamports);
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account_info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Allocate {
+                    account_to_allocate: account_info.clone(),
+                },
+                signer_seeds,
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Assign {
+                    account_to_assign: account_info.clone(),
+                },
+                signer_seeds,
+            ),
+            program_id,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Convert a program-owned shard still in the earlier Borsh layout in place
+/// Returns false, touching nothing, if it is not in that layout
+pub(crate) fn upgrade_shard_layout(shard_info: &AccountInfo) -> Result<bool> {
+    require_keys_eq!(*shard_info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    if !UserVaultShard::has_legacy_layout(shard_info) {
+        return Ok(false);
+    }
+
+    UserVaultShard::rewrite_legacy_layout(&mut shard_info.try_borrow_mut_data()?)?;
+    shard_info.realloc(UserVaultShard::SIZE, false)?;
+    Ok(true)
+}
+
+/// Convert a program-owned index still in the earlier Borsh layout in place
+/// Returns false, touching nothing, if it is not in that layout
+pub(crate) fn upgrade_index_layout(index_info: &AccountInfo) -> Result<bool> {
+    require_keys_eq!(*index_info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    if !UserVaultIndex::has_legacy_layout(index_info) {
+        return Ok(false);
+    }
+
+    UserVaultIndex::rewrite_legacy_layout(&mut index_info.try_borrow_mut_data()?)?;
+    index_info.realloc(UserVaultIndex::MAX_SIZE, false)?;
+    Ok(true)
+}
+
+/// Mutably borrow a shard passed as a raw account, with the same owner and
+/// discriminator checks AccountLoader::load_mut performs
+fn borrow_shard_mut<'a>(
+    shard_info: &'a AccountInfo,
+    program_id: &Pubkey,
+) -> Result<std::cell::RefMut<'a, UserVaultShard>> {
+    require_keys_eq!(*shard_info.owner, *program_id, ErrorCode::AccountOwnedByWrongProgram);
+
+    // Shards in the earlier Borsh layout are longer and would be misread
+    require!(
+        shard_info.data_len() <= UserVaultShard::SIZE,
+        BlockDriveError::LegacyAccountLayout
+    );
+
+    let data = shard_info.try_borrow_mut_data()?;
+    require!(
+        data.len() >= UserVaultShard::SIZE,
+        ErrorCode::AccountDidNotDeserialize
+    );
+    require!(
+        data[..8] == UserVaultShard::DISCRIMINATOR,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+
+    Ok(std::cell::RefMut::map(data, |data| {
+        bytemuck::from_bytes_mut(&mut data[8..UserVaultShard::SIZE])
+    }))
+}
@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
-use crate::state::{UserVault, VaultStatus};
+use crate::state::{UserVault, UserVaultMaster, VaultStatus, VaultConfig, FileRecord, GuardianSet};
 use crate::errors::BlockDriveError;
-use crate::events::{VaultCreated, VaultFrozen, VaultUnfrozen, MasterKeyRotated};
+use crate::events::{
+    VaultCreated, VaultFrozen, VaultUnfrozen, MasterKeyRotated, VaultClosed, FreezeReason,
+};
 
 #[derive(Accounts)]
 pub struct InitializeVault<'info> {
@@ -14,6 +16,12 @@ pub struct InitializeVault<'info> {
     )]
     pub vault: Account<'info, UserVault>,
 
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
     #[account(mut)]
     pub owner: Signer<'info>,
 
@@ -24,7 +32,7 @@ pub struct InitializeVault<'info> {
 pub struct RotateMasterKey<'info> {
     #[account(
         mut,
-        seeds = [UserVault::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
         bump = vault.bump,
         has_one = owner @ BlockDriveError::Unauthorized
     )]
@@ -37,7 +45,7 @@ pub struct RotateMasterKey<'info> {
 pub struct FreezeVault<'info> {
     #[account(
         mut,
-        seeds = [UserVault::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
         bump = vault.bump,
         has_one = owner @ BlockDriveError::Unauthorized
     )]
@@ -46,16 +54,65 @@ pub struct FreezeVault<'info> {
     pub owner: Signer<'info>,
 }
 
+/// Freeze by the config authority or one of the vault's guardians
+/// Pass the UserVault, the UserVaultMaster, or both
+#[derive(Accounts)]
+pub struct EmergencyFreezeVault<'info> {
+    #[account(
+        mut,
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Option<Account<'info, UserVault>>,
+
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump
+    )]
+    pub vault_master: Option<Account<'info, UserVaultMaster>>,
+
+    /// Required when the authority is a guardian rather than the config authority
+    #[account(
+        seeds = [GuardianSet::SEED_PREFIX, guardian_set.vault_id.as_ref()],
+        bump = guardian_set.bump
+    )]
+    pub guardian_set: Option<Account<'info, GuardianSet>>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct UnfreezeVault<'info> {
     #[account(
         mut,
-        seeds = [UserVault::SEED_PREFIX, owner.key().as_ref()],
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
         bump = vault.bump,
         has_one = owner @ BlockDriveError::Unauthorized
     )]
     pub vault: Account<'info, UserVault>,
 
+    #[account(mut)]
     pub owner: Signer<'info>,
 }
 
@@ -67,6 +124,8 @@ pub fn initialize_vault(
     let vault = &mut ctx.accounts.vault;
     let clock = Clock::get()?;
 
+    require!(!ctx.accounts.config.is_paused, BlockDriveError::ProgramPaused);
+
     vault.bump = ctx.bumps.vault;
     vault.owner = ctx.accounts.owner.key();
     vault.master_key_commitment = master_key_commitment;
@@ -75,7 +134,10 @@ pub fn initialize_vault(
     vault.created_at = clock.unix_timestamp;
     vault.updated_at = clock.unix_timestamp;
     vault.status = VaultStatus::Active;
-    vault.reserved = [0u8; 64];
+    vault.vault_id = ctx.accounts.owner.key();
+    vault.key_epoch = 0;
+    vault.frozen_at = 0;
+    vault.reserved = [0u8; 20];
 
     emit!(VaultCreated {
         owner: ctx.accounts.owner.key(),
@@ -96,11 +158,11 @@ pub fn rotate_master_key(
 
     require!(vault.is_active(), BlockDriveError::VaultNotActive);
 
-    vault.master_key_commitment = new_commitment;
-    vault.updated_at = clock.unix_timestamp;
+    vault.rotate_master_key(new_commitment, clock.unix_timestamp);
 
     emit!(MasterKeyRotated {
         vault: vault.key(),
+        key_epoch: vault.key_epoch,
         timestamp: clock.unix_timestamp,
     });
 
@@ -114,23 +176,91 @@ pub fn freeze_vault(ctx: Context<FreezeVault>) -> Result<()> {
 
     require!(vault.is_active(), BlockDriveError::VaultNotActive);
 
-    vault.status = VaultStatus::Frozen;
-    vault.updated_at = clock.unix_timestamp;
+    vault.freeze(clock.unix_timestamp);
 
     emit!(VaultFrozen {
         vault: vault.key(),
+        frozen_by: ctx.accounts.owner.key(),
+        reason: FreezeReason::OwnerRequest,
+        unfreeze_after: vault.unfreeze_after(),
         timestamp: clock.unix_timestamp,
     });
 
     Ok(())
 }
 
-/// Unfreeze a vault
+/// Freeze a vault on behalf of its owner (compromised wallet, compliance hold)
+/// The authority must be the config authority or a guardian of the vault
+pub fn emergency_freeze_vault(ctx: Context<EmergencyFreezeVault>, reason: u8) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    let clock = Clock::get()?;
+
+    let reason = FreezeReason::from_u8(reason).ok_or(BlockDriveError::InvalidFreezeReason)?;
+
+    let vault_seed = match (&ctx.accounts.vault, &ctx.accounts.vault_master) {
+        (Some(vault), Some(vault_master)) => {
+            require_keys_eq!(
+                vault.vault_seed(),
+                vault_master.vault_seed(),
+                BlockDriveError::Unauthorized
+            );
+            vault.vault_seed()
+        }
+        (Some(vault), None) => vault.vault_seed(),
+        (None, Some(vault_master)) => vault_master.vault_seed(),
+        (None, None) => return err!(BlockDriveError::VaultAccountRequired),
+    };
+
+    let is_guardian = ctx.accounts.guardian_set.as_ref().is_some_and(|guardian_set| {
+        guardian_set.vault_id == vault_seed && guardian_set.guardian_position(&authority).is_some()
+    });
+    require!(
+        authority == ctx.accounts.config.authority || is_guardian,
+        BlockDriveError::NotFreezeAuthority
+    );
+
+    let mut frozen = false;
+    if let Some(vault) = ctx.accounts.vault.as_mut() {
+        if vault.is_active() {
+            vault.freeze(clock.unix_timestamp);
+            frozen = true;
+            emit!(VaultFrozen {
+                vault: vault.key(),
+                frozen_by: authority,
+                reason,
+                unfreeze_after: vault.unfreeze_after(),
+                timestamp: clock.unix_timestamp,
+            });
+        }
+    }
+    if let Some(vault_master) = ctx.accounts.vault_master.as_mut() {
+        if vault_master.is_active() {
+            vault_master.freeze(clock.unix_timestamp);
+            frozen = true;
+            emit!(VaultFrozen {
+                vault: vault_master.key(),
+                frozen_by: authority,
+                reason,
+                unfreeze_after: vault_master.unfreeze_after(),
+                timestamp: clock.unix_timestamp,
+            });
+        }
+    }
+    require!(frozen, BlockDriveError::VaultNotActive);
+
+    Ok(())
+}
+
+/// Unfreeze a vault once the unfreeze timelock has elapsed
 pub fn unfreeze_vault(ctx: Context<UnfreezeVault>) -> Result<()> {
     let vault = &mut ctx.accounts.vault;
     let clock = Clock::get()?;
 
     require!(vault.is_frozen(), BlockDriveError::VaultNotActive);
+    require!(
+        vault.can_unfreeze(clock.unix_timestamp),
+        BlockDriveError::UnfreezeTimelockActive
+    );
 
     vault.status = VaultStatus::Active;
     vault.updated_at = clock.unix_timestamp;
@@ -142,3 +272,85 @@ pub fn unfreeze_vault(ctx: Context<UnfreezeVault>) -> Result<()> {
 
     Ok(())
 }
+
+/// Close a vault and recover its rent
+/// With `force`, remaining FileRecords are closed from remaining accounts first
+pub fn close_vault<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>,
+    force: bool,
+) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let owner = ctx.accounts.owner.to_account_info();
+    let clock = Clock::get()?;
+
+    require!(vault.is_active(), BlockDriveError::VaultNotActive);
+
+    let mut files_closed: u64 = 0;
+    if force {
+        for account in ctx.remaining_accounts.iter() {
+            let file_record = Account::<FileRecord>::try_from(account)?;
+            require!(
+                file_record.vault == vault.key() && file_record.owner == owner.key(),
+                BlockDriveError::Unauthorized
+            );
+            // Delegations are seeded off the record and would be orphaned
+            require!(
+                file_record.delegation_count == 0,
+                BlockDriveError::FileHasDelegations
+            );
+            require!(
+                file_record.retained_versions == 0,
+                BlockDriveError::FileHasVersions
+            );
+
+            vault.remove_file(file_record.file_size, clock.unix_timestamp);
+            file_record.close(owner.clone())?;
+            files_closed = files_closed.saturating_add(1);
+        }
+    }
+
+    require!(vault.file_count == 0, BlockDriveError::VaultNotEmpty);
+
+    emit!(VaultClosed {
+        owner: owner.key(),
+        vault: vault.key(),
+        files_closed,
+        forced: force,
+        timestamp: clock.unix_timestamp,
+    });
+
+    // Account is closed automatically via `close = owner` constraint
+    Ok(())
+}
+
+/// Reject the operation if the file's vault (a UserVault or UserVaultMaster) is frozen
+pub fn require_vault_not_frozen(vault: &AccountInfo) -> Result<()> {
+    let (_, frozen) = read_vault_state(vault)?;
+    require!(!frozen, BlockDriveError::VaultFrozen);
+    Ok(())
+}
+
+/// Reject an owner write to a file unless its vault is unfrozen and still owned
+/// by `file_owner` (the FileRecord's owner). After a recovery or transfer the
+/// record is read-only until sync_owner_records re-points it, so the previous
+/// owner loses write access immediately
+pub fn require_vault_writable(vault: &AccountInfo, file_owner: &Pubkey) -> Result<()> {
+    let (owner, frozen) = read_vault_state(vault)?;
+    require_keys_eq!(owner, *file_owner, BlockDriveError::Unauthorized);
+    require!(!frozen, BlockDriveError::VaultFrozen);
+    Ok(())
+}
+
+/// Current owner and frozen status of a UserVault or UserVaultMaster
+fn read_vault_state(vault: &AccountInfo) -> Result<(Pubkey, bool)> {
+    require_keys_eq!(*vault.owner, crate::ID, BlockDriveError::Unauthorized);
+
+    let data = vault.try_borrow_data()?;
+    match UserVault::try_deserialize(&mut &data[..]) {
+        Ok(vault) => Ok((vault.owner, vault.is_frozen())),
+        Err(_) => {
+            let vault_master = UserVaultMaster::try_deserialize(&mut &data[..])?;
+            Ok((vault_master.owner, vault_master.is_frozen()))
+        }
+    }
+}
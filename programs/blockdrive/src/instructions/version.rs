@@ -0,0 +1,252 @@
+use anchor_lang::prelude::*;
+use crate::state::{UserVault, UserVaultMaster, FileRecord, FileVersion, VaultConfig};
+use crate::errors::BlockDriveError;
+use crate::events::{FileVersionCommitted, FileVersionsPruned};
+use crate::instructions::vault::require_vault_writable;
+
+// =============================================================================
+// ACCOUNT CONTEXTS
+// =============================================================================
+
+/// Replace a legacy file's content, keeping the old content as a FileVersion
+#[derive(Accounts)]
+pub struct CommitNewVersion<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = FileVersion::SIZE,
+        seeds = [
+            FileVersion::SEED_PREFIX,
+            file_record.key().as_ref(),
+            &file_record.version.to_le_bytes()
+        ],
+        bump
+    )]
+    pub file_version: Account<'info, FileVersion>,
+
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        has_one = vault @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    #[account(
+        mut,
+        seeds = [UserVault::SEED_PREFIX, vault.vault_seed().as_ref()],
+        bump = vault.bump,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub vault: Account<'info, UserVault>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Replace a sharded file's content, keeping the old content as a FileVersion
+#[derive(Accounts)]
+pub struct CommitNewVersionSharded<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = FileVersion::SIZE,
+        seeds = [
+            FileVersion::SEED_PREFIX,
+            file_record.key().as_ref(),
+            &file_record.version.to_le_bytes()
+        ],
+        bump
+    )]
+    pub file_version: Account<'info, FileVersion>,
+
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = file_record.vault == vault_master.key() @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    #[account(
+        mut,
+        seeds = [UserVaultMaster::SEED_PREFIX, vault_master.vault_seed().as_ref()],
+        bump = vault_master.bump,
+        has_one = owner @ BlockDriveError::Unauthorized,
+        constraint = !vault_master.is_frozen() @ BlockDriveError::VaultFrozen
+    )]
+    pub vault_master: Account<'info, UserVaultMaster>,
+
+    #[account(
+        seeds = [VaultConfig::SEED_PREFIX],
+        bump = config.bump
+    )]
+    pub config: Account<'info, VaultConfig>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Close old FileVersion snapshots of a file (legacy or sharded)
+/// The FileVersion accounts to close are passed as remaining accounts
+#[derive(Accounts)]
+pub struct PruneVersions<'info> {
+    #[account(
+        mut,
+        has_one = owner @ BlockDriveError::Unauthorized
+    )]
+    pub file_record: Account<'info, FileRecord>,
+
+    /// CHECK: The file's UserVault or UserVaultMaster; owner and frozen status checked in handler
+    #[account(address = file_record.vault @ BlockDriveError::Unauthorized)]
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+// =============================================================================
+// INSTRUCTION HANDLERS
+// =============================================================================
+
+/// Commit a new version of a legacy file
+/// The current content moves to a FileVersion PDA; the FileRecord (and its
+/// delegations) stays in place as the head
+pub fn commit_new_version(
+    ctx: Context<CommitNewVersion>,
+    file_size: u64,
+    encrypted_size: u64,
+    encryption_commitment: [u8; 32],
+    critical_bytes_commitment: [u8; 32],
+    primary_cid: [u8; 64],
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let vault = &mut ctx.accounts.vault;
+    let file_record = &mut ctx.accounts.file_record;
+    let file_version = &mut ctx.accounts.file_version;
+    let clock = Clock::get()?;
+
+    require!(!config.is_paused, BlockDriveError::ProgramPaused);
+    require!(vault.is_active(), BlockDriveError::VaultNotActive);
+    require!(file_record.is_active(), BlockDriveError::FileNotActive);
+    require!(config.is_file_size_allowed(file_size), BlockDriveError::FileSizeExceeded);
+
+    file_version.snapshot(
+        ctx.bumps.file_version,
+        file_record.key(),
+        file_record,
+        clock.unix_timestamp,
+    );
+    vault.resize_file(file_record.file_size, file_size, clock.unix_timestamp);
+    file_record.replace_content(
+        file_size,
+        encrypted_size,
+        encryption_commitment,
+        critical_bytes_commitment,
+        primary_cid,
+        vault.key_epoch,
+    );
+
+    emit!(FileVersionCommitted {
+        vault: vault.key(),
+        file_record: file_record.key(),
+        file_version: file_version.key(),
+        version: file_record.version,
+        file_size,
+        encrypted_size,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Commit a new version of a sharded file
+pub fn commit_new_version_sharded(
+    ctx: Context<CommitNewVersionSharded>,
+    file_size: u64,
+    encrypted_size: u64,
+    encryption_commitment: [u8; 32],
+    critical_bytes_commitment: [u8; 32],
+    primary_cid: [u8; 64],
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let vault_master = &mut ctx.accounts.vault_master;
+    let file_record = &mut ctx.accounts.file_record;
+    let file_version = &mut ctx.accounts.file_version;
+    let clock = Clock::get()?;
+
+    require!(!config.is_paused, BlockDriveError::ProgramPaused);
+    require!(file_record.is_active(), BlockDriveError::FileNotActive);
+    require!(config.is_file_size_allowed(file_size), BlockDriveError::FileSizeExceeded);
+
+    file_version.snapshot(
+        ctx.bumps.file_version,
+        file_record.key(),
+        file_record,
+        clock.unix_timestamp,
+    );
+    vault_master.resize_file(file_record.file_size, file_size, clock.unix_timestamp);
+    file_record.replace_content(
+        file_size,
+        encrypted_size,
+        encryption_commitment,
+        critical_bytes_commitment,
+        primary_cid,
+        vault_master.key_epoch,
+    );
+
+    emit!(FileVersionCommitted {
+        vault: vault_master.key(),
+        file_record: file_record.key(),
+        file_version: file_version.key(),
+        version: file_record.version,
+        file_size,
+        encrypted_size,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Close FileVersion snapshots and return their rent to the owner
+pub fn prune_versions<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PruneVersions<'info>>,
+) -> Result<()> {
+    let file_record = &mut ctx.accounts.file_record;
+    let owner = ctx.accounts.owner.to_account_info();
+    let clock = Clock::get()?;
+
+    require_vault_writable(&ctx.accounts.vault, &file_record.owner)?;
+
+    let mut versions_closed: u16 = 0;
+    for account in ctx.remaining_accounts.iter() {
+        let file_version = Account::<FileVersion>::try_from(account)?;
+        require_keys_eq!(
+            file_version.file_record,
+            file_record.key(),
+            BlockDriveError::InvalidFileVersion
+        );
+
+        file_version.close(owner.clone())?;
+        versions_closed = versions_closed.saturating_add(1);
+    }
+
+    file_record.retained_versions = file_record.retained_versions.saturating_sub(versions_closed);
+
+    emit!(FileVersionsPruned {
+        file_record: file_record.key(),
+        versions_closed,
+        retained_versions: file_record.retained_versions,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -7,6 +7,7 @@ pub mod events;
 pub mod transfer_hook;
 
 use instructions::*;
+use state::{FileLocation, FileRegistration};
 use transfer_hook::*;
 
 declare_id!("BLKDrv1111111111111111111111111111111111111");
@@ -36,35 +37,42 @@ pub mod blockdrive {
         instructions::vault::freeze_vault(ctx)
     }
 
-    /// Unfreeze a vault
+    /// Freeze a vault without the owner's signature
+    /// Callable by the config authority or a guardian of the vault; blocks
+    /// registrations and delegations until the owner unfreezes after the timelock
+    ///
+    /// # Arguments
+    /// * `reason` - FreezeReason code (0 = owner request, 1 = compromised wallet,
+    ///   2 = compliance, 3 = other)
+    pub fn emergency_freeze_vault(ctx: Context<EmergencyFreezeVault>, reason: u8) -> Result<()> {
+        instructions::vault::emergency_freeze_vault(ctx, reason)
+    }
+
+    /// Unfreeze a vault (owner only, after the unfreeze timelock)
     pub fn unfreeze_vault(ctx: Context<UnfreezeVault>) -> Result<()> {
         instructions::vault::unfreeze_vault(ctx)
     }
 
+    /// Close a vault and recover its rent
+    /// Refuses while files remain, unless `force` is set and the remaining
+    /// FileRecords are passed as remaining accounts to be closed first
+    pub fn close_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseVault<'info>>,
+        force: bool,
+    ) -> Result<()> {
+        instructions::vault::close_vault(ctx, force)
+    }
+
     /// Register a new encrypted file
     pub fn register_file(
         ctx: Context<RegisterFile>,
         file_id: [u8; 16],
-        filename_hash: [u8; 32],
-        file_size: u64,
-        encrypted_size: u64,
-        mime_type_hash: [u8; 32],
-        security_level: u8,
-        encryption_commitment: [u8; 32],
-        critical_bytes_commitment: [u8; 32],
-        primary_cid: [u8; 64],
+        registration: FileRegistration,
     ) -> Result<()> {
         instructions::file::register_file(
             ctx,
             file_id,
-            filename_hash,
-            file_size,
-            encrypted_size,
-            mime_type_hash,
-            security_level,
-            encryption_commitment,
-            critical_bytes_commitment,
-            primary_cid,
+            registration,
         )
     }
 
@@ -77,22 +85,89 @@ pub mod blockdrive {
         instructions::file::update_file_storage(ctx, redundancy_cid, provider_count)
     }
 
+    /// Update a file's filename and MIME type hashes (e.g. after a rename)
+    /// Works for legacy and sharded files; the signer must be the owner or a
+    /// relayer with a session holding OperationFlags::UPDATE
+    ///
+    /// # Arguments
+    /// * `expected_nonce` - Session nonce, ignored when the owner signs
+    pub fn update_file_metadata(
+        ctx: Context<UpdateFileMetadata>,
+        filename_hash: [u8; 32],
+        mime_type_hash: [u8; 32],
+        expected_nonce: u64,
+    ) -> Result<()> {
+        instructions::file::update_file_metadata(ctx, filename_hash, mime_type_hash, expected_nonce)
+    }
+
     /// Archive a file
-    pub fn archive_file(ctx: Context<ArchiveFile>) -> Result<()> {
+    ///
+    /// # Remaining Accounts
+    /// The file's remaining delegations as (delegation, grantor) pairs; each is
+    /// closed with its rent returned to the grantor. Files with more delegations
+    /// than fit in one transaction are cleared first with revoke_file_delegations
+    pub fn archive_file<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ArchiveFile<'info>>,
+    ) -> Result<()> {
         instructions::file::archive_file(ctx)
     }
 
+    /// Restore an archived file to active
+    pub fn unarchive_file(ctx: Context<UnarchiveFile>) -> Result<()> {
+        instructions::file::unarchive_file(ctx)
+    }
+
     /// Delete a file record
-    pub fn delete_file(ctx: Context<DeleteFile>) -> Result<()> {
+    ///
+    /// # Remaining Accounts
+    /// The file's remaining delegations as (delegation, grantor) pairs; each is
+    /// closed with its rent returned to the grantor. Files with more delegations
+    /// than fit in one transaction are cleared first with revoke_file_delegations
+    pub fn delete_file<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DeleteFile<'info>>,
+    ) -> Result<()> {
         instructions::file::delete_file(ctx)
     }
 
+    /// Re-encrypt a file under the current master key
+    /// Replaces the commitments and CIDs and bumps the file's key epoch
+    pub fn reencrypt_file(
+        ctx: Context<ReencryptFile>,
+        encryption_commitment: [u8; 32],
+        critical_bytes_commitment: [u8; 32],
+        primary_cid: [u8; 64],
+        redundancy_cid: [u8; 64],
+    ) -> Result<()> {
+        instructions::file::reencrypt_file(
+            ctx,
+            encryption_commitment,
+            critical_bytes_commitment,
+            primary_cid,
+            redundancy_cid,
+        )
+    }
+
+    /// Return the FileRecords (passed as remaining accounts, max 31) that are
+    /// still encrypted under an older key epoch than the vault's
+    pub fn find_stale_files<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FindStaleFiles<'info>>,
+    ) -> Result<Vec<Pubkey>> {
+        instructions::file::find_stale_files(ctx)
+    }
+
     /// Record file access
+    /// The owner may always record access; anyone else must pass their
+    /// Delegation, which must be active, unexpired and allow downloads
+    ///
+    /// # Remaining Accounts
+    /// For a sub-delegation, its ancestors nearest first; the access is
+    /// rejected if any of them has been revoked
     pub fn record_access(ctx: Context<RecordAccess>) -> Result<()> {
         instructions::file::record_access(ctx)
     }
 
     /// Create a new file delegation
+    /// Only the file owner can grant directly; Reshare grantees use reshare_delegation
     pub fn create_delegation(
         ctx: Context<CreateDelegation>,
         encrypted_file_key: [u8; 128],
@@ -112,6 +187,17 @@ pub mod blockdrive {
         instructions::delegation::revoke_delegation(ctx)
     }
 
+    /// Revoke any delegations of a file as its owner, sub-delegations included
+    ///
+    /// # Remaining Accounts
+    /// (delegation, grantor) pairs of this file; each is closed with its rent
+    /// returned to the grantor. Can be repeated in batches
+    pub fn revoke_file_delegations<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RevokeFileDelegations<'info>>,
+    ) -> Result<()> {
+        instructions::delegation::revoke_file_delegations(ctx)
+    }
+
     /// Update delegation permissions
     pub fn update_delegation(
         ctx: Context<UpdateDelegation>,
@@ -121,6 +207,56 @@ pub mod blockdrive {
         instructions::delegation::update_delegation(ctx, permission_level, expires_at)
     }
 
+    /// Re-share a file as a grantee holding PermissionLevel::Reshare
+    /// The child delegation is capped at the parent's permission level and
+    /// expiry, and is limited to MAX_RESHARE_DEPTH levels below the owner
+    ///
+    /// # Remaining Accounts
+    /// Ancestors of parent_delegation, nearest first (none if the parent was
+    /// granted by the owner)
+    pub fn reshare_delegation(
+        ctx: Context<ReshareDelegation>,
+        encrypted_file_key: [u8; 128],
+        permission_level: u8,
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::delegation::reshare_delegation(
+            ctx,
+            encrypted_file_key,
+            permission_level,
+            expires_at,
+        )
+    }
+
+    /// Close an expired delegation (permissionless)
+    /// Rent goes back to the grantor; the caller is paid config.cleanup_tip
+    /// from the tip pool funded with fund_cleanup_tips
+    pub fn close_expired_delegation(ctx: Context<CloseExpiredDelegation>) -> Result<()> {
+        instructions::delegation::close_expired_delegation(ctx)
+    }
+
+    /// Accept/claim a delegation (grantee signs)
+    ///
+    /// # Remaining Accounts
+    /// For a sub-delegation, its ancestors nearest first; it cannot be accepted
+    /// once any of them has been revoked
+    pub fn accept_delegation(ctx: Context<AcceptDelegation>) -> Result<()> {
+        instructions::delegation::accept_delegation(ctx)
+    }
+
+    /// Reject a delegation (grantee signs)
+    /// Closes the delegation and refunds its rent to the grantor
+    pub fn reject_delegation(ctx: Context<RejectDelegation>) -> Result<()> {
+        instructions::delegation::reject_delegation(ctx)
+    }
+
+    /// Grow a delegation created before re-sharing existed
+    /// Must be called once on such delegations before any other delegation
+    /// instruction can load them; any wallet can pay for it
+    pub fn resize_delegation(ctx: Context<ResizeDelegation>) -> Result<()> {
+        instructions::delegation::resize_delegation(ctx)
+    }
+
     // =========================================================================
     // MEMBERSHIP INSTRUCTIONS
     // =========================================================================
@@ -189,6 +325,9 @@ pub mod blockdrive {
     /// # Seeds
     /// - vault_master: ["vault_master", owner_pubkey]
     /// - vault_index: ["vault_index", vault_master_pubkey]
+    ///
+    /// The index page is only allocated here; follow with extend_vault_index
+    /// (page 0) in the same transaction
     pub fn initialize_vault_master(ctx: Context<InitializeVaultMaster>) -> Result<()> {
         instructions::sharding::initialize_vault_master(ctx)
     }
@@ -201,10 +340,38 @@ pub mod blockdrive {
     ///
     /// # Seeds
     /// - vault_shard: ["vault_shard", vault_master_pubkey, shard_index]
+    ///
+    /// Shards 10+ require the covering directory page (see create_directory_page)
     pub fn create_shard(ctx: Context<CreateShard>, shard_index: u8) -> Result<()> {
         instructions::sharding::create_shard(ctx, shard_index)
     }
 
+    /// Add an overflow directory page and its index page
+    /// Each page raises the shard limit by 10 (1000 files), up to 250 shards
+    ///
+    /// # Arguments
+    /// * `page_index` - Must equal vault_master.directory_pages + 1 (sequential creation)
+    ///
+    /// # Seeds
+    /// - vault_directory: ["vault_directory", vault_master_pubkey, page_index]
+    /// - vault_index: ["vault_index", vault_master_pubkey, page_index]
+    ///
+    /// The index page is only allocated here; follow with extend_vault_index
+    /// in the same transaction
+    pub fn create_directory_page(ctx: Context<CreateDirectoryPage>, page_index: u8) -> Result<()> {
+        instructions::sharding::create_directory_page(ctx, page_index)
+    }
+
+    /// Grow an allocated index page to full size and initialize it
+    /// Index pages are larger than a program can allocate in one instruction,
+    /// so their creating instruction allocates part and this adds the rest
+    ///
+    /// # Arguments
+    /// * `page_index` - Page of the index (0 for the base index)
+    pub fn extend_vault_index(ctx: Context<ExtendVaultIndex>, page_index: u8) -> Result<()> {
+        instructions::sharding::extend_vault_index(ctx, page_index)
+    }
+
     /// Register a new file to a specific shard
     /// This is the sharded version of register_file for multi-PDA storage
     ///
@@ -219,30 +386,393 @@ pub mod blockdrive {
         ctx: Context<RegisterFileSharded>,
         file_id: [u8; 16],
         shard_index: u8,
-        filename_hash: [u8; 32],
+        registration: FileRegistration,
+    ) -> Result<()> {
+        instructions::sharding::register_file_sharded(
+            ctx,
+            file_id,
+            shard_index,
+            registration,
+        )
+    }
+
+    /// Register a new file without choosing a shard
+    /// The program targets vault_master.active_shard_index. When the active one
+    /// is full, next_shard is either a later shard with free slots (which becomes
+    /// active) or a new shard at index total_shards, created and funded by payer
+    ///
+    /// # Arguments
+    /// * `file_id` - Unique 16-byte file identifier
+    /// * Other params same as register_file
+    ///
+    /// # Seeds
+    /// - active_shard: ["vault_shard", vault_master_pubkey, active_shard_index]
+    /// - next_shard: ["vault_shard", vault_master_pubkey, shard_index]
+    /// - file_record: ["file", vault_master_pubkey, file_id]
+    pub fn register_file_auto(
+        ctx: Context<RegisterFileAuto>,
+        file_id: [u8; 16],
+        registration: FileRegistration,
+    ) -> Result<()> {
+        instructions::sharding::register_file_auto(
+            ctx,
+            file_id,
+            registration,
+        )
+    }
+
+    /// Delete a file from a sharded vault and recover rent
+    /// Clears the shard slot, removes the index entry and updates master totals
+    ///
+    /// # Remaining Accounts
+    /// The file's remaining delegations as (delegation, grantor) pairs; each is
+    /// closed with its rent returned to the grantor. Files with more delegations
+    /// than fit in one transaction are cleared first with revoke_file_delegations
+    pub fn delete_file_sharded<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DeleteFileSharded<'info>>,
+    ) -> Result<()> {
+        instructions::sharding::delete_file_sharded(ctx)
+    }
+
+    /// Compact a shard so its files occupy a dense prefix of slots
+    /// Index entries of every moved file are updated to the new slot
+    pub fn compact_shard(ctx: Context<CompactShard>) -> Result<()> {
+        instructions::sharding::compact_shard(ctx)
+    }
+
+    /// Move a file record into another shard's first free slot
+    /// Pass target_index only when the target shard is on a different index page
+    pub fn rebalance_file(ctx: Context<RebalanceFile>) -> Result<()> {
+        instructions::sharding::rebalance_file(ctx)
+    }
+
+    /// Archive a shard so it no longer accepts new files
+    pub fn archive_shard(ctx: Context<ArchiveShard>) -> Result<()> {
+        instructions::sharding::archive_shard(ctx)
+    }
+
+    /// Close an empty shard and return its rent to the owner
+    /// The active shard can only be closed once every other shard is closed
+    pub fn close_shard(ctx: Context<CloseShard>) -> Result<()> {
+        instructions::sharding::close_shard(ctx)
+    }
+
+    /// Close an empty Vault Master and its index, returning rent to the owner
+    /// Requires total_file_count == 0 and every shard closed
+    ///
+    /// # Remaining Accounts
+    /// Overflow pages in order: directory page N, index page N for N = 1..=directory_pages
+    pub fn close_vault_master<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseVaultMaster<'info>>,
+    ) -> Result<()> {
+        instructions::sharding::close_vault_master(ctx)
+    }
+
+    /// Update a sharded file's storage with redundancy info
+    pub fn update_file_storage_sharded(
+        ctx: Context<UpdateFileStorageSharded>,
+        redundancy_cid: [u8; 64],
+        provider_count: u8,
+    ) -> Result<()> {
+        instructions::sharding::update_file_storage_sharded(ctx, redundancy_cid, provider_count)
+    }
+
+    /// Archive a sharded file
+    ///
+    /// # Remaining Accounts
+    /// The file's remaining delegations as (delegation, grantor) pairs; each is
+    /// closed with its rent returned to the grantor. Files with more delegations
+    /// than fit in one transaction are cleared first with revoke_file_delegations
+    pub fn archive_file_sharded<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ArchiveFileSharded<'info>>,
+    ) -> Result<()> {
+        instructions::sharding::archive_file_sharded(ctx)
+    }
+
+    /// Restore an archived sharded file to active
+    pub fn unarchive_file_sharded(ctx: Context<UnarchiveFileSharded>) -> Result<()> {
+        instructions::sharding::unarchive_file_sharded(ctx)
+    }
+
+    /// Resolve a file_id to its shard, slot and FileRecord pubkey
+    /// View instruction: the FileLocation is returned via set_return_data,
+    /// so clients can simulate it and CPI callers can read it back
+    ///
+    /// # Arguments
+    /// * `file_id` - 16-byte file identifier to look up
+    ///
+    /// The vault_index passed must be the page covering the file's shard
+    pub fn query_file_location(
+        ctx: Context<QueryFileLocation>,
+        file_id: [u8; 16],
+    ) -> Result<FileLocation> {
+        instructions::sharding::query_file_location(ctx, file_id)
+    }
+
+    /// Rotate the master key commitment of a sharded vault
+    /// Also used to record the commitment on masters created without one
+    pub fn rotate_master_key_sharded(
+        ctx: Context<RotateMasterKeySharded>,
+        new_commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::sharding::rotate_master_key_sharded(ctx, new_commitment)
+    }
+
+    /// Freeze a sharded vault (emergency)
+    /// Every sharded write is rejected until the vault is unfrozen
+    pub fn freeze_vault_sharded(ctx: Context<FreezeVaultSharded>) -> Result<()> {
+        instructions::sharding::freeze_vault_sharded(ctx)
+    }
+
+    /// Unfreeze a sharded vault (owner only, after the unfreeze timelock)
+    pub fn unfreeze_vault_sharded(ctx: Context<UnfreezeVaultSharded>) -> Result<()> {
+        instructions::sharding::unfreeze_vault_sharded(ctx)
+    }
+
+    /// Re-encrypt a sharded file under the current master key
+    /// Replaces the commitments and CIDs and bumps the file's key epoch
+    pub fn reencrypt_file_sharded(
+        ctx: Context<ReencryptFileSharded>,
+        encryption_commitment: [u8; 32],
+        critical_bytes_commitment: [u8; 32],
+        primary_cid: [u8; 64],
+        redundancy_cid: [u8; 64],
+    ) -> Result<()> {
+        instructions::sharding::reencrypt_file_sharded(
+            ctx,
+            encryption_commitment,
+            critical_bytes_commitment,
+            primary_cid,
+            redundancy_cid,
+        )
+    }
+
+    /// Return the FileRecords of a sharded vault (passed as remaining accounts,
+    /// max 31) that are still encrypted under an older key epoch than the master's
+    pub fn find_stale_files_sharded<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FindStaleFilesSharded<'info>>,
+    ) -> Result<Vec<Pubkey>> {
+        instructions::sharding::find_stale_files_sharded(ctx)
+    }
+
+    /// Grow a Vault Master created before status and key commitment existed
+    /// Must be called once on such masters before any other sharded instruction
+    /// (including a recovery or transfer); any wallet can pay for it
+    pub fn resize_vault_master(ctx: Context<ResizeVaultMaster>) -> Result<()> {
+        instructions::sharding::resize_vault_master(ctx)
+    }
+
+    /// Convert a shard created before the zero-copy layout
+    /// Must be called once on such shards before they can take or move files;
+    /// closing a shard and syncing its owner convert it on the way
+    pub fn migrate_shard_layout(ctx: Context<MigrateShardLayout>) -> Result<()> {
+        instructions::sharding::migrate_shard_layout(ctx)
+    }
+
+    /// Convert a vault index page created before the zero-copy layout
+    /// Must be called once on such pages before files can be registered or
+    /// looked up; closing the Vault Master accepts either layout
+    pub fn migrate_index_layout(ctx: Context<MigrateIndexLayout>) -> Result<()> {
+        instructions::sharding::migrate_index_layout(ctx)
+    }
+
+    // =========================================================================
+    // MIGRATION INSTRUCTIONS (Legacy UserVault -> Sharded)
+    // =========================================================================
+
+    /// Start migrating a legacy UserVault to sharded storage
+    /// Creates the Vault Master and allocates the Vault Index if they don't exist
+    /// yet; follow with extend_vault_index (page 0) in the same transaction
+    pub fn migrate_legacy_vault(ctx: Context<MigrateLegacyVault>) -> Result<()> {
+        instructions::migration::migrate_legacy_vault(ctx)
+    }
+
+    /// Move one legacy FileRecord into a shard slot and index entry
+    /// The record is re-created under ["file", vault_master, file_id] and the
+    /// legacy account is closed
+    ///
+    /// # Remaining Accounts
+    /// A (legacy, new) pair for each of the file's delegations, then for each
+    /// retained FileVersion. Each is re-created under the new record and the
+    /// legacy account closed
+    pub fn migrate_legacy_file<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateLegacyFile<'info>>,
+    ) -> Result<()> {
+        instructions::migration::migrate_legacy_file(ctx)
+    }
+
+    /// Close the legacy UserVault after all files have been migrated
+    pub fn finalize_legacy_migration(ctx: Context<FinalizeLegacyMigration>) -> Result<()> {
+        instructions::migration::finalize_legacy_migration(ctx)
+    }
+
+    // =========================================================================
+    // FILE VERSIONING INSTRUCTIONS
+    // =========================================================================
+
+    /// Commit a new version of a legacy file
+    /// The previous content is kept in a FileVersion PDA
+    /// (["file_version", file_record, version]); the FileRecord stays the head,
+    /// so its file_id, created_at and delegations are preserved
+    pub fn commit_new_version(
+        ctx: Context<CommitNewVersion>,
         file_size: u64,
         encrypted_size: u64,
-        mime_type_hash: [u8; 32],
-        security_level: u8,
         encryption_commitment: [u8; 32],
         critical_bytes_commitment: [u8; 32],
         primary_cid: [u8; 64],
     ) -> Result<()> {
-        instructions::sharding::register_file_sharded(
+        instructions::version::commit_new_version(
+            ctx,
+            file_size,
+            encrypted_size,
+            encryption_commitment,
+            critical_bytes_commitment,
+            primary_cid,
+        )
+    }
+
+    /// Commit a new version of a sharded file
+    pub fn commit_new_version_sharded(
+        ctx: Context<CommitNewVersionSharded>,
+        file_size: u64,
+        encrypted_size: u64,
+        encryption_commitment: [u8; 32],
+        critical_bytes_commitment: [u8; 32],
+        primary_cid: [u8; 64],
+    ) -> Result<()> {
+        instructions::version::commit_new_version_sharded(
             ctx,
-            file_id,
-            shard_index,
-            filename_hash,
             file_size,
             encrypted_size,
-            mime_type_hash,
-            security_level,
             encryption_commitment,
             critical_bytes_commitment,
             primary_cid,
         )
     }
 
+    /// Close old FileVersion snapshots and reclaim their rent
+    ///
+    /// # Remaining Accounts
+    /// FileVersion accounts of this file to close
+    pub fn prune_versions<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PruneVersions<'info>>,
+    ) -> Result<()> {
+        instructions::version::prune_versions(ctx)
+    }
+
+    // =========================================================================
+    // OWNERSHIP & RECOVERY INSTRUCTIONS
+    // =========================================================================
+
+    /// Create an M-of-N guardian set for a vault
+    ///
+    /// # Arguments
+    /// * `vault_id` - Owner-independent vault id (the wallet that created the vault)
+    /// * `guardians` - 1-10 guardian wallets (excluding the owner)
+    /// * `threshold` - Approvals required to recover (M)
+    /// * `recovery_delay` - Timelock in seconds after the threshold is reached (min 24h)
+    ///
+    /// # Seeds
+    /// - guardian_set: ["guardians", vault_id]
+    pub fn initialize_guardians(
+        ctx: Context<InitializeGuardians>,
+        vault_id: Pubkey,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        recovery_delay: i64,
+    ) -> Result<()> {
+        instructions::ownership::initialize_guardians(ctx, vault_id, guardians, threshold, recovery_delay)
+    }
+
+    /// Replace a vault's guardians, threshold or recovery delay
+    /// Rejected while a recovery is pending or the vault is frozen
+    pub fn update_guardians(
+        ctx: Context<UpdateGuardians>,
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        recovery_delay: i64,
+    ) -> Result<()> {
+        instructions::ownership::update_guardians(ctx, guardians, threshold, recovery_delay)
+    }
+
+    /// Open a recovery moving the vault to a new wallet (guardian only)
+    ///
+    /// # Seeds
+    /// - recovery_request: ["recovery", guardian_set_pubkey]
+    pub fn initiate_recovery(
+        ctx: Context<InitiateRecovery>,
+        new_owner: Pubkey,
+        new_master_key_commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::ownership::initiate_recovery(ctx, new_owner, new_master_key_commitment)
+    }
+
+    /// Approve a pending recovery (guardian only)
+    pub fn approve_recovery(ctx: Context<ApproveRecovery>) -> Result<()> {
+        instructions::ownership::approve_recovery(ctx)
+    }
+
+    /// Execute a recovery once approved and past its timelock
+    /// Signed by the new owner; rotates owner and master key commitment.
+    /// Both the UserVault and UserVaultMaster addresses of the vault id are
+    /// required, whether or not each exists
+    pub fn execute_recovery(ctx: Context<ExecuteRecovery>) -> Result<()> {
+        instructions::ownership::execute_recovery(ctx)
+    }
+
+    /// Cancel a pending recovery (owner only, not while the vault is frozen)
+    pub fn cancel_recovery(ctx: Context<CancelRecovery>) -> Result<()> {
+        instructions::ownership::cancel_recovery(ctx)
+    }
+
+    /// Propose moving a vault to a new wallet (step 1 of 2)
+    /// The UserVault, UserVaultMaster and GuardianSet addresses of the vault id
+    /// are required; rejected while the vault is frozen or a recovery is pending
+    ///
+    /// # Seeds
+    /// - owner_transfer: ["owner_transfer", vault_id]
+    pub fn propose_owner_transfer(
+        ctx: Context<ProposeOwnerTransfer>,
+        vault_id: Pubkey,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        instructions::ownership::propose_owner_transfer(ctx, vault_id, new_owner)
+    }
+
+    /// Accept a proposed transfer as the new owner (step 2 of 2)
+    /// The UserVault, UserVaultMaster and GuardianSet addresses of the vault id
+    /// are required, whether or not each exists
+    ///
+    /// # Arguments
+    /// * `new_master_key_commitment` - Commitment to the new wallet's master key
+    ///
+    /// # Remaining Accounts
+    /// Optional first batch of records to re-point (same layout as sync_owner_records)
+    pub fn accept_owner_transfer<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AcceptOwnerTransfer<'info>>,
+        new_master_key_commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::ownership::accept_owner_transfer(ctx, new_master_key_commitment)
+    }
+
+    /// Withdraw a pending ownership transfer
+    pub fn cancel_owner_transfer(ctx: Context<CancelOwnerTransfer>) -> Result<()> {
+        instructions::ownership::cancel_owner_transfer(ctx)
+    }
+
+    /// Re-point records at the vault's current owner after a recovery or transfer
+    ///
+    /// # Remaining Accounts
+    /// FileRecords, Delegations (after their FileRecord, which may be passed
+    /// again if it was synced in an earlier batch), UserVaultShards,
+    /// UserVaultIndex pages and UserVaultDirectory pages of the vault
+    pub fn sync_owner_records<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SyncOwnerRecords<'info>>,
+    ) -> Result<()> {
+        instructions::ownership::sync_owner_records(ctx)
+    }
+
     // =========================================================================
     // SESSION DELEGATION INSTRUCTIONS (Phase 1.2 - Relayer Authority)
     // =========================================================================
@@ -286,6 +816,13 @@ pub mod blockdrive {
         instructions::session::close_session(ctx)
     }
 
+    /// Close an expired or revoked session (permissionless)
+    /// Rent goes back to the owner; the caller is paid config.cleanup_tip
+    /// from the tip pool funded with fund_cleanup_tips
+    pub fn close_expired_session(ctx: Context<CloseExpiredSession>) -> Result<()> {
+        instructions::session::close_expired_session(ctx)
+    }
+
     /// Validate that a session is active and can perform an operation
     /// Used by relayers to check session status before submitting transactions
     pub fn validate_session(
@@ -296,6 +833,77 @@ pub mod blockdrive {
         instructions::session::validate_session(ctx, operation, expected_nonce)
     }
 
+    // =========================================================================
+    // CONFIG INSTRUCTIONS (Admin)
+    // =========================================================================
+
+    /// Initialize the global config (one-time, upgrade authority only)
+    ///
+    /// # Arguments
+    /// * `file_registration_fee` - Fee in lamports charged per file registration
+    /// * `delegation_fee` - Fee in lamports charged per delegation
+    /// * `max_file_size` - Maximum file size in bytes (0 = unlimited)
+    /// * `max_files_per_vault` - Maximum files per vault (0 = unlimited)
+    ///
+    /// # Seeds
+    /// - config: ["config"]
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        file_registration_fee: u64,
+        delegation_fee: u64,
+        max_file_size: u64,
+        max_files_per_vault: u64,
+    ) -> Result<()> {
+        instructions::config::initialize_config(
+            ctx,
+            file_registration_fee,
+            delegation_fee,
+            max_file_size,
+            max_files_per_vault,
+        )
+    }
+
+    /// Update program fees
+    pub fn update_fees(
+        ctx: Context<UpdateConfig>,
+        file_registration_fee: u64,
+        delegation_fee: u64,
+    ) -> Result<()> {
+        instructions::config::update_fees(ctx, file_registration_fee, delegation_fee)
+    }
+
+    /// Update file size and files-per-vault limits (0 = unlimited)
+    pub fn update_limits(
+        ctx: Context<UpdateConfig>,
+        max_file_size: u64,
+        max_files_per_vault: u64,
+    ) -> Result<()> {
+        instructions::config::update_limits(ctx, max_file_size, max_files_per_vault)
+    }
+
+    /// Set the tip paid to callers of close_expired_delegation / close_expired_session
+    /// Capped at MAX_CLEANUP_TIP lamports
+    pub fn update_cleanup_tip(ctx: Context<UpdateConfig>, cleanup_tip: u64) -> Result<()> {
+        instructions::config::update_cleanup_tip(ctx, cleanup_tip)
+    }
+
+    /// Add lamports to the cleanup tip pool held by the config
+    /// Any wallet can fund it, typically the fee collector out of collected fees
+    pub fn fund_cleanup_tips(ctx: Context<FundCleanupTips>, amount: u64) -> Result<()> {
+        instructions::config::fund_cleanup_tips(ctx, amount)
+    }
+
+    /// Change the wallet that receives registration and delegation fees
+    pub fn update_fee_collector(ctx: Context<UpdateFeeCollector>) -> Result<()> {
+        instructions::config::update_fee_collector(ctx)
+    }
+
+    /// Pause/unpause program
+    /// While paused, vault creation, file registration and delegation are rejected
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        instructions::config::set_paused(ctx, paused)
+    }
+
     // =========================================================================
     // TRANSFER HOOK INSTRUCTIONS
     // =========================================================================
@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
 
+/// Maximum re-share depth (0 = granted by the owner, 1..=3 = sub-delegations)
+pub const MAX_RESHARE_DEPTH: u8 = 3;
+
 /// Permission level enumeration
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PermissionLevel {
@@ -21,6 +24,9 @@ impl PermissionLevel {
 }
 
 /// Delegation PDA - manages file access delegation between users
+/// Sub-delegations (depth > 0) are granted by a Reshare grantee; their parent is
+/// the delegation seeded by the same file record and the child's grantor, and
+/// is recorded in parent_delegation
 /// Seeds: ["delegation", file_record_pubkey, grantee_pubkey]
 #[account]
 pub struct Delegation {
@@ -61,8 +67,11 @@ pub struct Delegation {
     /// Last access timestamp
     pub last_accessed_at: i64,
 
-    /// Reserved for future use
-    pub reserved: [u8; 32],
+    /// Re-share depth (0 = granted directly by the file owner)
+    pub depth: u8,
+
+    /// Delegation this one was re-shared from (default for depth 0)
+    pub parent_delegation: Pubkey,
 }
 
 impl Delegation {
@@ -80,7 +89,12 @@ impl Delegation {
         1 +   // is_accepted
         8 +   // access_count
         8 +   // last_accessed_at
-        32;   // reserved
+        1 +   // depth
+        32;   // parent_delegation
+
+    /// Account size before re-sharing added depth and parent_delegation in place
+    /// of the reserved bytes
+    pub const LEGACY_SIZE: usize = Self::SIZE - 1;
 
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"delegation";
@@ -110,6 +124,25 @@ impl Delegation {
     pub fn can_reshare(&self) -> bool {
         matches!(self.permission_level, PermissionLevel::Reshare)
     }
+
+    /// Check if this is a sub-delegation created by a Reshare grantee
+    pub fn is_reshared(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// Check if this delegation was granted by a previous owner of the file
+    /// Only the owner grants at depth 0, so such a grantor other than `owner`
+    /// is stale after a recovery or transfer
+    pub fn needs_owner_sync(&self, owner: &Pubkey) -> bool {
+        !self.is_reshared() && self.grantor != *owner
+    }
+
+    /// Check that a child's permission level and expiry stay within this delegation's
+    pub fn covers(&self, permission_level: PermissionLevel, expires_at: i64) -> bool {
+        let within_level = (permission_level as u8) <= (self.permission_level as u8);
+        let within_expiry = self.expires_at == 0 || (expires_at > 0 && expires_at <= self.expires_at);
+        within_level && within_expiry
+    }
 }
 
 impl Default for Delegation {
@@ -127,7 +160,62 @@ impl Default for Delegation {
             is_accepted: false,
             access_count: 0,
             last_accessed_at: 0,
-            reserved: [0u8; 32],
+            depth: 0,
+            parent_delegation: Pubkey::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reshare_capped_by_parent() {
+        let parent = Delegation {
+            permission_level: PermissionLevel::Download,
+            expires_at: 1_000,
+            ..Default::default()
+        };
+
+        assert!(parent.covers(PermissionLevel::View, 500));
+        assert!(parent.covers(PermissionLevel::Download, 1_000));
+        assert!(!parent.covers(PermissionLevel::Reshare, 500));
+        assert!(!parent.covers(PermissionLevel::View, 1_001));
+        // A parent with an expiry cannot grant a never-expiring child
+        assert!(!parent.covers(PermissionLevel::View, 0));
+
+        let open_parent = Delegation {
+            permission_level: PermissionLevel::Reshare,
+            ..Default::default()
+        };
+        assert!(open_parent.covers(PermissionLevel::Reshare, 0));
+    }
+
+    #[test]
+    fn test_owner_sync_moves_only_owner_grants() {
+        let first_owner = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+
+        let granted = Delegation {
+            grantor: first_owner,
+            ..Default::default()
+        };
+        assert!(granted.needs_owner_sync(&new_owner));
+
+        // Already synced in an earlier batch
+        let synced = Delegation {
+            grantor: new_owner,
+            ..Default::default()
+        };
+        assert!(!synced.needs_owner_sync(&new_owner));
+
+        // Sub-delegations stay with the re-sharing grantee
+        let reshared = Delegation {
+            grantor: Pubkey::new_unique(),
+            depth: 1,
+            ..Default::default()
+        };
+        assert!(!reshared.needs_owner_sync(&new_owner));
+    }
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::errors::BlockDriveError;
 
 /// File status enumeration
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
@@ -29,6 +30,21 @@ impl SecurityLevel {
     }
 }
 
+/// Metadata and commitments supplied when registering a file
+/// Fields are in the order the register instructions used to take them as
+/// separate arguments, so the instruction data layout is unchanged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FileRegistration {
+    pub filename_hash: [u8; 32],
+    pub file_size: u64,
+    pub encrypted_size: u64,
+    pub mime_type_hash: [u8; 32],
+    pub security_level: u8,
+    pub encryption_commitment: [u8; 32],
+    pub critical_bytes_commitment: [u8; 32],
+    pub primary_cid: [u8; 64],
+}
+
 /// FileRecord PDA - stores individual file metadata and commitments on-chain
 /// Seeds: ["file", vault_pubkey, file_id]
 #[account]
@@ -90,8 +106,17 @@ pub struct FileRecord {
     /// Number of active delegations
     pub delegation_count: u8,
 
+    /// Master key epoch the file was last encrypted under
+    pub key_epoch: u32,
+
+    /// Current (head) version number, 0 as registered
+    pub version: u32,
+
+    /// Number of FileVersion snapshots still open for this file
+    pub retained_versions: u16,
+
     /// Reserved for future use
-    pub reserved: [u8; 32],
+    pub reserved: [u8; 22],
 }
 
 impl FileRecord {
@@ -116,11 +141,49 @@ impl FileRecord {
         1 +   // status
         1 +   // is_shared
         1 +   // delegation_count
-        32;   // reserved
+        4 +   // key_epoch
+        4 +   // version
+        2 +   // retained_versions
+        22;   // reserved
 
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"file";
 
+    /// Build the record for a newly registered file (head version 0, no delegations)
+    /// `key_epoch` is the vault's current epoch, which the content is encrypted under
+    pub fn new(
+        bump: u8,
+        vault: Pubkey,
+        owner: Pubkey,
+        file_id: [u8; 16],
+        registration: &FileRegistration,
+        key_epoch: u32,
+        timestamp: i64,
+    ) -> Result<Self> {
+        let security_level = SecurityLevel::from_u8(registration.security_level)
+            .ok_or(BlockDriveError::InvalidSecurityLevel)?;
+
+        Ok(Self {
+            bump,
+            vault,
+            owner,
+            file_id,
+            filename_hash: registration.filename_hash,
+            file_size: registration.file_size,
+            encrypted_size: registration.encrypted_size,
+            mime_type_hash: registration.mime_type_hash,
+            security_level,
+            encryption_commitment: registration.encryption_commitment,
+            critical_bytes_commitment: registration.critical_bytes_commitment,
+            primary_cid: registration.primary_cid,
+            provider_count: 1,
+            created_at: timestamp,
+            accessed_at: timestamp,
+            key_epoch,
+            ..Self::default()
+        })
+    }
+
     /// Check if file is active
     pub fn is_active(&self) -> bool {
         self.status == FileStatus::Active
@@ -131,11 +194,52 @@ impl FileRecord {
         self.status == FileStatus::Archived
     }
 
+    /// Check if the file is encrypted under an older master key
+    pub fn needs_reencryption(&self, current_epoch: u32) -> bool {
+        self.key_epoch < current_epoch
+    }
+
+    /// Replace the head content with a new version
+    /// The caller snapshots the previous content into a FileVersion first; the new
+    /// content is encrypted under the vault's current key epoch
+    pub fn replace_content(
+        &mut self,
+        file_size: u64,
+        encrypted_size: u64,
+        encryption_commitment: [u8; 32],
+        critical_bytes_commitment: [u8; 32],
+        primary_cid: [u8; 64],
+        key_epoch: u32,
+    ) {
+        self.file_size = file_size;
+        self.encrypted_size = encrypted_size;
+        self.encryption_commitment = encryption_commitment;
+        self.critical_bytes_commitment = critical_bytes_commitment;
+        self.primary_cid = primary_cid;
+        self.redundancy_cid = [0u8; 64];
+        self.provider_count = 1;
+        self.key_epoch = key_epoch;
+        self.version = self.version.saturating_add(1);
+        self.retained_versions = self.retained_versions.saturating_add(1);
+    }
+
+    /// Check if `grantor` may create a top-level delegation (only the owner can;
+    /// everyone else goes through reshare_delegation)
+    pub fn can_delegate(&self, grantor: &Pubkey) -> bool {
+        self.owner == *grantor
+    }
+
     /// Update access timestamp
     pub fn record_access(&mut self, timestamp: i64) {
         self.accessed_at = timestamp;
     }
 
+    /// Check if another delegation fits in the delegation count
+    /// The count must stay exact, or delete/archive could leave delegations orphaned
+    pub fn can_add_delegation(&self) -> bool {
+        self.delegation_count < u8::MAX
+    }
+
     /// Add a delegation
     pub fn add_delegation(&mut self) {
         self.delegation_count = self.delegation_count.saturating_add(1);
@@ -173,7 +277,81 @@ impl Default for FileRecord {
             status: FileStatus::Active,
             is_shared: false,
             delegation_count: 0,
-            reserved: [0u8; 32],
+            key_epoch: 0,
+            version: 0,
+            retained_versions: 0,
+            reserved: [0u8; 22],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_owner_can_delegate() {
+        let owner = Pubkey::new_unique();
+        let record = FileRecord {
+            owner,
+            ..Default::default()
+        };
+
+        assert!(record.can_delegate(&owner));
+        assert!(!record.can_delegate(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_new_version_moves_to_current_epoch() {
+        let mut record = FileRecord {
+            key_epoch: 1,
+            provider_count: 2,
+            redundancy_cid: [9u8; 64],
+            ..Default::default()
+        };
+        assert!(record.needs_reencryption(3));
+
+        record.replace_content(10, 20, [1u8; 32], [2u8; 32], [3u8; 64], 3);
+
+        assert!(!record.needs_reencryption(3));
+        assert_eq!(record.version, 1);
+        assert_eq!(record.retained_versions, 1);
+        assert_eq!(record.provider_count, 1);
+        assert_eq!(record.redundancy_cid, [0u8; 64]);
+    }
+
+    #[test]
+    fn test_delegation_count_is_capped() {
+        let mut record = FileRecord {
+            delegation_count: u8::MAX - 1,
+            ..Default::default()
+        };
+        assert!(record.can_add_delegation());
+
+        record.add_delegation();
+        assert!(!record.can_add_delegation());
+
+        record.remove_delegation();
+        assert!(record.can_add_delegation());
+        assert!(record.is_shared);
+    }
+
+    #[test]
+    fn test_revoking_every_delegation_unshares_file() {
+        let mut record = FileRecord::default();
+        for _ in 0..3 {
+            record.add_delegation();
         }
+
+        // Delete and archive revoke in batches until the count reaches zero
+        record.remove_delegation();
+        record.remove_delegation();
+        assert!(record.is_shared);
+        record.remove_delegation();
+        assert_eq!(record.delegation_count, 0);
+        assert!(!record.is_shared);
+
+        record.remove_delegation();
+        assert_eq!(record.delegation_count, 0);
     }
 }
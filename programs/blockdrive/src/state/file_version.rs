@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use crate::state::FileRecord;
+
+/// FileVersion PDA - snapshot of a FileRecord's content before a new version replaced it
+/// The FileRecord stays the head; older versions hang off it by number
+/// Seeds: ["file_version", file_record_pubkey, version (u32 LE)]
+#[account]
+pub struct FileVersion {
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Head FileRecord this version belongs to
+    pub file_record: Pubkey,
+
+    /// Version number of this snapshot (0 = as originally registered)
+    pub version: u32,
+
+    /// Original file size in bytes
+    pub file_size: u64,
+
+    /// Encrypted file size in bytes
+    pub encrypted_size: u64,
+
+    /// Encryption commitment: SHA256(encrypted_content)
+    pub encryption_commitment: [u8; 32],
+
+    /// Critical bytes commitment: SHA256(critical_bytes)
+    pub critical_bytes_commitment: [u8; 32],
+
+    /// Primary storage provider CID/hash
+    pub primary_cid: [u8; 64],
+
+    /// Redundancy storage CID (optional)
+    pub redundancy_cid: [u8; 64],
+
+    /// Master key epoch the version was encrypted under
+    pub key_epoch: u32,
+
+    /// When a newer version replaced this one
+    pub superseded_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl FileVersion {
+    /// Account size for rent calculation
+    pub const SIZE: usize = 8 +   // discriminator
+        1 +   // bump
+        32 +  // file_record
+        4 +   // version
+        8 +   // file_size
+        8 +   // encrypted_size
+        32 +  // encryption_commitment
+        32 +  // critical_bytes_commitment
+        64 +  // primary_cid
+        64 +  // redundancy_cid
+        4 +   // key_epoch
+        8 +   // superseded_at
+        32;   // reserved
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"file_version";
+
+    /// Capture the head record's current content before it is replaced
+    pub fn snapshot(&mut self, bump: u8, file_record: Pubkey, head: &FileRecord, timestamp: i64) {
+        self.bump = bump;
+        self.file_record = file_record;
+        self.version = head.version;
+        self.file_size = head.file_size;
+        self.encrypted_size = head.encrypted_size;
+        self.encryption_commitment = head.encryption_commitment;
+        self.critical_bytes_commitment = head.critical_bytes_commitment;
+        self.primary_cid = head.primary_cid;
+        self.redundancy_cid = head.redundancy_cid;
+        self.key_epoch = head.key_epoch;
+        self.superseded_at = timestamp;
+        self.reserved = [0u8; 32];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_keeps_superseded_content() {
+        let mut head = FileRecord {
+            file_size: 10,
+            primary_cid: [1u8; 64],
+            key_epoch: 2,
+            ..Default::default()
+        };
+        let mut file_version = FileVersion {
+            bump: 0,
+            file_record: Pubkey::default(),
+            version: 0,
+            file_size: 0,
+            encrypted_size: 0,
+            encryption_commitment: [0u8; 32],
+            critical_bytes_commitment: [0u8; 32],
+            primary_cid: [0u8; 64],
+            redundancy_cid: [0u8; 64],
+            key_epoch: 0,
+            superseded_at: 0,
+            reserved: [0u8; 32],
+        };
+        let file_record = Pubkey::new_unique();
+
+        file_version.snapshot(255, file_record, &head, 50);
+        head.replace_content(20, 40, [4u8; 32], [5u8; 32], [6u8; 64], 3);
+
+        assert_eq!(file_version.file_record, file_record);
+        assert_eq!(file_version.version, 0);
+        assert_eq!(file_version.file_size, 10);
+        assert_eq!(file_version.primary_cid, [1u8; 64]);
+        assert_eq!(file_version.key_epoch, 2);
+        assert_eq!(file_version.superseded_at, 50);
+        assert_eq!(head.version, 1);
+        assert_eq!(head.file_size, 20);
+    }
+}
@@ -0,0 +1,169 @@
+use anchor_lang::prelude::*;
+use crate::errors::BlockDriveError;
+
+/// Maximum number of guardians per vault
+pub const MAX_GUARDIANS: usize = 10;
+
+/// Minimum delay between reaching the approval threshold and executing a recovery
+pub const MIN_RECOVERY_DELAY: i64 = 24 * 60 * 60;
+
+/// GuardianSet PDA - M-of-N guardians that can recover a vault to a new wallet
+/// Shared by a user's UserVault and UserVaultMaster (both use the same vault_id)
+/// Seeds: ["guardians", vault_id]
+#[account]
+pub struct GuardianSet {
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Owner-independent vault id (the wallet that created the vault)
+    pub vault_id: Pubkey,
+
+    /// Current vault owner (follows recoveries and transfers)
+    pub owner: Pubkey,
+
+    /// Guardian wallets; only the first `guardian_count` are in use
+    pub guardians: [Pubkey; MAX_GUARDIANS],
+
+    /// Number of guardians configured (1-10)
+    pub guardian_count: u8,
+
+    /// Approvals required to recover (M of N)
+    pub threshold: u8,
+
+    /// Seconds between reaching the threshold and execution
+    /// Gives the owner a window to cancel a malicious recovery
+    pub recovery_delay: i64,
+
+    /// Whether a RecoveryRequest is currently open
+    pub pending_recovery: bool,
+
+    /// Creation timestamp
+    pub created_at: i64,
+
+    /// Last activity timestamp
+    pub updated_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl GuardianSet {
+    /// Account size for rent calculation
+    pub const SIZE: usize = 8 +       // discriminator
+        1 +                            // bump
+        32 +                           // vault_id
+        32 +                           // owner
+        (32 * MAX_GUARDIANS) +         // guardians (10 * 32 = 320)
+        1 +                            // guardian_count
+        1 +                            // threshold
+        8 +                            // recovery_delay
+        1 +                            // pending_recovery
+        8 +                            // created_at
+        8 +                            // updated_at
+        32;                            // reserved
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"guardians";
+
+    /// Replace the guardian list, threshold and delay
+    pub fn set_guardians(
+        &mut self,
+        guardians: &[Pubkey],
+        threshold: u8,
+        recovery_delay: i64,
+        timestamp: i64,
+    ) -> Result<()> {
+        require!(
+            !guardians.is_empty() && guardians.len() <= MAX_GUARDIANS,
+            BlockDriveError::InvalidGuardianSet
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= guardians.len(),
+            BlockDriveError::InvalidGuardianThreshold
+        );
+        require!(
+            recovery_delay >= MIN_RECOVERY_DELAY,
+            BlockDriveError::RecoveryDelayTooShort
+        );
+
+        for (i, guardian) in guardians.iter().enumerate() {
+            require!(
+                *guardian != Pubkey::default()
+                    && *guardian != self.owner
+                    && !guardians[..i].contains(guardian),
+                BlockDriveError::InvalidGuardianSet
+            );
+        }
+
+        self.guardians = [Pubkey::default(); MAX_GUARDIANS];
+        self.guardians[..guardians.len()].copy_from_slice(guardians);
+        self.guardian_count = guardians.len() as u8;
+        self.threshold = threshold;
+        self.recovery_delay = recovery_delay;
+        self.updated_at = timestamp;
+
+        Ok(())
+    }
+
+    /// Position of a guardian in the set (used as its approval bit)
+    pub fn guardian_position(&self, guardian: &Pubkey) -> Option<u8> {
+        self.guardians[..self.guardian_count as usize]
+            .iter()
+            .position(|g| g == guardian)
+            .map(|index| index as u8)
+    }
+}
+
+impl Default for GuardianSet {
+    fn default() -> Self {
+        Self {
+            bump: 0,
+            vault_id: Pubkey::default(),
+            owner: Pubkey::default(),
+            guardians: [Pubkey::default(); MAX_GUARDIANS],
+            guardian_count: 0,
+            threshold: 0,
+            recovery_delay: 0,
+            pending_recovery: false,
+            created_at: 0,
+            updated_at: 0,
+            reserved: [0u8; 32],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guardians_exclude_owner_and_duplicates() {
+        let owner = Pubkey::new_unique();
+        let guardian_a = Pubkey::new_unique();
+        let guardian_b = Pubkey::new_unique();
+        let mut guardian_set = GuardianSet {
+            owner,
+            ..Default::default()
+        };
+
+        assert!(guardian_set
+            .set_guardians(&[guardian_a, owner], 1, MIN_RECOVERY_DELAY, 0)
+            .is_err());
+        assert!(guardian_set
+            .set_guardians(&[guardian_a, guardian_a], 1, MIN_RECOVERY_DELAY, 0)
+            .is_err());
+        assert!(guardian_set
+            .set_guardians(&[guardian_a, guardian_b], 3, MIN_RECOVERY_DELAY, 0)
+            .is_err());
+        assert!(guardian_set
+            .set_guardians(&[guardian_a, guardian_b], 2, MIN_RECOVERY_DELAY - 1, 0)
+            .is_err());
+
+        guardian_set
+            .set_guardians(&[guardian_a, guardian_b], 2, MIN_RECOVERY_DELAY, 10)
+            .unwrap();
+        assert_eq!(guardian_set.guardian_count, 2);
+        assert_eq!(guardian_set.guardian_position(&guardian_b), Some(1));
+        assert_eq!(guardian_set.guardian_position(&owner), None);
+    }
+}
@@ -6,6 +6,12 @@ pub mod user_vault_master;
 pub mod user_vault_shard;
 pub mod vault_index;
 pub mod session_delegation;
+pub mod vault_config;
+pub mod vault_directory;
+pub mod guardian_set;
+pub mod recovery_request;
+pub mod owner_transfer;
+pub mod file_version;
 
 pub use user_vault::*;
 pub use file_record::*;
@@ -15,3 +21,9 @@ pub use user_vault_master::*;
 pub use user_vault_shard::*;
 pub use vault_index::*;
 pub use session_delegation::*;
+pub use vault_config::*;
+pub use vault_directory::*;
+pub use guardian_set::*;
+pub use recovery_request::*;
+pub use owner_transfer::*;
+pub use file_version::*;
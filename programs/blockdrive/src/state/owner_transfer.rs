@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+/// OwnerTransfer PDA - a pending two-step ownership transfer
+/// Created by the current owner, consumed when the new owner accepts
+/// Seeds: ["owner_transfer", vault_id]
+#[account]
+#[derive(Default)]
+pub struct OwnerTransfer {
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Owner-independent vault id being transferred
+    pub vault_id: Pubkey,
+
+    /// Owner proposing the transfer (receives the rent back)
+    pub current_owner: Pubkey,
+
+    /// Wallet that must accept the transfer
+    pub new_owner: Pubkey,
+
+    /// Proposal timestamp
+    pub proposed_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl OwnerTransfer {
+    /// Account size for rent calculation
+    pub const SIZE: usize = 8 +   // discriminator
+        1 +   // bump
+        32 +  // vault_id
+        32 +  // current_owner
+        32 +  // new_owner
+        8 +   // proposed_at
+        32;   // reserved
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"owner_transfer";
+}
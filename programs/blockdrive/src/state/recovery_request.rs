@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use crate::errors::BlockDriveError;
+
+/// RecoveryRequest PDA - an open guardian recovery for a vault
+/// Only one request can be open per GuardianSet at a time
+/// Seeds: ["recovery", guardian_set_pubkey]
+#[account]
+#[derive(Default)]
+pub struct RecoveryRequest {
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// GuardianSet this request belongs to
+    pub guardian_set: Pubkey,
+
+    /// Owner at the time the recovery was initiated
+    pub old_owner: Pubkey,
+
+    /// Wallet that will own the vault after execution
+    pub new_owner: Pubkey,
+
+    /// Master key commitment for the new wallet's key
+    pub new_master_key_commitment: [u8; 32],
+
+    /// Guardian that opened the request (receives the rent back)
+    pub initiator: Pubkey,
+
+    /// Bitmap of approving guardians (bit = guardian position)
+    pub approvals: u16,
+
+    /// Number of approvals collected
+    pub approval_count: u8,
+
+    /// Initiation timestamp
+    pub initiated_at: i64,
+
+    /// Earliest execution time (0 until the threshold is reached)
+    pub executable_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl RecoveryRequest {
+    /// Account size for rent calculation
+    pub const SIZE: usize = 8 +   // discriminator
+        1 +   // bump
+        32 +  // guardian_set
+        32 +  // old_owner
+        32 +  // new_owner
+        32 +  // new_master_key_commitment
+        32 +  // initiator
+        2 +   // approvals
+        1 +   // approval_count
+        8 +   // initiated_at
+        8 +   // executable_at
+        32;   // reserved
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"recovery";
+
+    /// Record a guardian approval
+    /// Starts the timelock once the threshold is reached
+    pub fn approve(
+        &mut self,
+        guardian_position: u8,
+        threshold: u8,
+        recovery_delay: i64,
+        timestamp: i64,
+    ) -> Result<()> {
+        let bit = 1u16 << guardian_position;
+        require!(
+            self.approvals & bit == 0,
+            BlockDriveError::RecoveryAlreadyApproved
+        );
+
+        self.approvals |= bit;
+        self.approval_count = self.approval_count.saturating_add(1);
+
+        if self.approval_count >= threshold && self.executable_at == 0 {
+            self.executable_at = timestamp.saturating_add(recovery_delay);
+        }
+
+        Ok(())
+    }
+
+    /// Check if the threshold is met and the timelock has elapsed
+    pub fn is_executable(&self, threshold: u8, timestamp: i64) -> bool {
+        self.approval_count >= threshold
+            && self.executable_at != 0
+            && timestamp >= self.executable_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timelock_starts_at_threshold() {
+        let mut request = RecoveryRequest::default();
+        let delay = 100;
+
+        request.approve(0, 2, delay, 10).unwrap();
+        assert_eq!(request.executable_at, 0);
+        assert!(request.approve(0, 2, delay, 20).is_err());
+
+        request.approve(3, 2, delay, 50).unwrap();
+        assert_eq!(request.executable_at, 150);
+        assert!(!request.is_executable(2, 149));
+        assert!(request.is_executable(2, 150));
+    }
+}
@@ -1,5 +1,9 @@
 use anchor_lang::prelude::*;
 
+/// Minimum time a vault stays frozen before the owner can unfreeze it
+/// Gives guardians time to recover a vault whose owner wallet is compromised
+pub const UNFREEZE_TIMELOCK: i64 = 48 * 60 * 60;
+
 /// Vault status enumeration
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
 pub enum VaultStatus {
@@ -10,7 +14,7 @@ pub enum VaultStatus {
 }
 
 /// UserVault PDA - stores user's master key commitment and vault configuration
-/// Seeds: ["vault", owner_pubkey]
+/// Seeds: ["vault", vault_id] (vault_id = the wallet that created the vault)
 #[account]
 #[derive(Default)]
 pub struct UserVault {
@@ -39,8 +43,20 @@ pub struct UserVault {
     /// Vault status
     pub status: VaultStatus,
 
+    /// Owner-independent vault id used for PDA derivation
+    /// Pinned to the creating wallet so ownership can move without re-deriving
+    /// Default for vaults created before this field existed (owner is used instead)
+    pub vault_id: Pubkey,
+
+    /// Master key generation, incremented on every rotation
+    /// Files whose key_epoch is below this still use an older key
+    pub key_epoch: u32,
+
+    /// When the vault was last frozen (0 = never)
+    pub frozen_at: i64,
+
     /// Reserved for future use
-    pub reserved: [u8; 64],
+    pub reserved: [u8; 20],
 }
 
 impl UserVault {
@@ -54,11 +70,37 @@ impl UserVault {
         8 +   // created_at
         8 +   // updated_at
         1 +   // status
-        64;   // reserved
+        32 +  // vault_id
+        4 +   // key_epoch
+        8 +   // frozen_at
+        20;   // reserved
 
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"vault";
 
+    /// Key the vault PDA is derived from
+    pub fn vault_seed(&self) -> Pubkey {
+        if self.vault_id == Pubkey::default() {
+            self.owner
+        } else {
+            self.vault_id
+        }
+    }
+
+    /// Move the vault to a new owner, pinning the vault id first
+    pub fn set_owner(&mut self, new_owner: Pubkey, timestamp: i64) {
+        self.vault_id = self.vault_seed();
+        self.owner = new_owner;
+        self.updated_at = timestamp;
+    }
+
+    /// Replace the master key commitment and advance the key epoch
+    pub fn rotate_master_key(&mut self, new_commitment: [u8; 32], timestamp: i64) {
+        self.master_key_commitment = new_commitment;
+        self.key_epoch = self.key_epoch.saturating_add(1);
+        self.updated_at = timestamp;
+    }
+
     /// Check if vault is active
     pub fn is_active(&self) -> bool {
         self.status == VaultStatus::Active
@@ -69,6 +111,23 @@ impl UserVault {
         self.status == VaultStatus::Frozen
     }
 
+    /// Freeze the vault, starting the unfreeze timelock
+    pub fn freeze(&mut self, timestamp: i64) {
+        self.status = VaultStatus::Frozen;
+        self.frozen_at = timestamp;
+        self.updated_at = timestamp;
+    }
+
+    /// Earliest time the vault can be unfrozen
+    pub fn unfreeze_after(&self) -> i64 {
+        self.frozen_at.saturating_add(UNFREEZE_TIMELOCK)
+    }
+
+    /// Check if the unfreeze timelock has elapsed
+    pub fn can_unfreeze(&self, current_timestamp: i64) -> bool {
+        self.is_frozen() && current_timestamp >= self.unfreeze_after()
+    }
+
     /// Increment file count and storage
     pub fn add_file(&mut self, file_size: u64, timestamp: i64) {
         self.file_count = self.file_count.saturating_add(1);
@@ -76,6 +135,12 @@ impl UserVault {
         self.updated_at = timestamp;
     }
 
+    /// Adjust storage when a file's content is replaced by a new version
+    pub fn resize_file(&mut self, old_size: u64, new_size: u64, timestamp: i64) {
+        self.total_storage = self.total_storage.saturating_sub(old_size).saturating_add(new_size);
+        self.updated_at = timestamp;
+    }
+
     /// Decrement file count and storage
     pub fn remove_file(&mut self, file_size: u64, timestamp: i64) {
         self.file_count = self.file_count.saturating_sub(1);
@@ -83,3 +148,59 @@ impl UserVault {
         self.updated_at = timestamp;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::FileRecord;
+
+    #[test]
+    fn test_owner_change_rotation_marks_files_stale() {
+        let mut vault = UserVault {
+            owner: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let file = FileRecord {
+            key_epoch: vault.key_epoch,
+            ..Default::default()
+        };
+        assert!(!file.needs_reencryption(vault.key_epoch));
+
+        // Recovery and transfer rotate alongside the owner change
+        vault.set_owner(Pubkey::new_unique(), 100);
+        vault.rotate_master_key([7u8; 32], 100);
+
+        assert_eq!(vault.key_epoch, 1);
+        assert_eq!(vault.master_key_commitment, [7u8; 32]);
+        assert!(file.needs_reencryption(vault.key_epoch));
+    }
+
+    #[test]
+    fn test_owner_transfers_keep_vault_seed() {
+        let first_owner = Pubkey::new_unique();
+        let mut vault = UserVault {
+            owner: first_owner,
+            ..Default::default()
+        };
+
+        vault.set_owner(Pubkey::new_unique(), 100);
+        let last_owner = Pubkey::new_unique();
+        vault.set_owner(last_owner, 200);
+
+        // The PDA stays derived from the first owner across every change
+        assert_eq!(vault.owner, last_owner);
+        assert_eq!(vault.vault_seed(), first_owner);
+    }
+
+    #[test]
+    fn test_unfreeze_waits_for_timelock() {
+        let mut vault = UserVault::default();
+        assert!(!vault.can_unfreeze(0));
+
+        vault.freeze(100);
+        assert!(vault.is_frozen());
+        assert_eq!(vault.unfreeze_after(), 100 + UNFREEZE_TIMELOCK);
+        assert!(!vault.can_unfreeze(100 + UNFREEZE_TIMELOCK - 1));
+        assert!(vault.can_unfreeze(100 + UNFREEZE_TIMELOCK));
+    }
+}
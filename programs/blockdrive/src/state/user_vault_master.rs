@@ -1,15 +1,24 @@
 use anchor_lang::prelude::*;
 use crate::errors::BlockDriveError;
+use crate::state::{VaultStatus, UNFREEZE_TIMELOCK};
 
-/// Maximum number of shards per user (100 files each = 1000 files max)
+/// Number of shard pointers stored inline on the master (100 files each = 1000 files)
+/// Further shards are tracked in UserVaultDirectory overflow pages of the same size
 pub const MAX_SHARDS: usize = 10;
 
+/// Maximum number of shards per user across the master and all directory pages
+/// Shard indices are single-byte PDA seeds (250 * 100 = 25,000 files max)
+pub const MAX_TOTAL_SHARDS: usize = 250;
+
+/// Maximum number of directory pages, including page 0 (the master itself)
+pub const MAX_DIRECTORY_PAGES: usize = MAX_TOTAL_SHARDS / MAX_SHARDS;
+
 /// Files per shard before creating new shard
 pub const FILES_PER_SHARD: u8 = 100;
 
 /// UserVaultMaster PDA - The "Controller" account for multi-PDA sharding
 /// This is the root account that tracks all shards for a user.
-/// Seeds: ["vault_master", owner_pubkey]
+/// Seeds: ["vault_master", vault_id] (vault_id = the wallet that created the vault)
 #[account]
 pub struct UserVaultMaster {
     /// Bump seed for PDA derivation
@@ -21,19 +30,20 @@ pub struct UserVaultMaster {
     /// Total number of files across ALL shards
     pub total_file_count: u64,
 
-    /// Number of active shards (0-9)
+    /// Number of shards created (0-250)
     pub total_shards: u8,
 
-    /// Index of the current shard receiving new files (0-9)
+    /// Index of the current shard receiving new files
     /// When a shard reaches 100 files, this increments
     pub active_shard_index: u8,
 
     /// Total storage used across all shards (bytes)
     pub total_storage: u64,
 
-    /// Array of Pubkeys pointing to UserVaultShard PDAs
+    /// Array of Pubkeys pointing to the first 10 UserVaultShard PDAs
     /// Index 0 = first shard, etc.
     /// Default Pubkey::default() means shard not yet created
+    /// Shards 10+ are tracked in UserVaultDirectory pages
     pub shard_pointers: [Pubkey; MAX_SHARDS],
 
     /// Vault creation timestamp
@@ -42,8 +52,31 @@ pub struct UserVaultMaster {
     /// Last activity timestamp
     pub updated_at: i64,
 
+    /// Number of overflow directory pages created (each adds 10 shards)
+    pub directory_pages: u8,
+
+    /// Number of shards that have been closed (rent reclaimed)
+    pub closed_shards: u8,
+
+    /// Owner-independent vault id used for PDA derivation
+    /// Default for masters created before this field existed (owner is used instead)
+    pub vault_id: Pubkey,
+
+    /// Vault status (frozen masters reject all writes)
+    pub status: VaultStatus,
+
+    /// Master key generation, incremented on every rotation
+    pub key_epoch: u32,
+
+    /// Hash of the wallet-derived master encryption key
+    /// SHA256(master_key) - all zeroes until first set via rotate_master_key_sharded
+    pub master_key_commitment: [u8; 32],
+
+    /// When the vault was last frozen (0 = never)
+    pub frozen_at: i64,
+
     /// Reserved for future use
-    pub reserved: [u8; 64],
+    pub reserved: [u8; 17],
 }
 
 impl UserVaultMaster {
@@ -58,7 +91,17 @@ impl UserVaultMaster {
         (32 * MAX_SHARDS) +            // shard_pointers (10 * 32 = 320)
         8 +                            // created_at
         8 +                            // updated_at
-        64;                            // reserved
+        1 +                            // directory_pages
+        1 +                            // closed_shards
+        32 +                           // vault_id
+        1 +                            // status
+        4 +                            // key_epoch
+        32 +                           // master_key_commitment
+        8 +                            // frozen_at
+        17;                            // reserved
+
+    /// Account size before status, key_epoch and master_key_commitment were added
+    pub const LEGACY_SIZE: usize = Self::SIZE - 32;
 
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"vault_master";
@@ -74,7 +117,64 @@ impl UserVaultMaster {
         self.shard_pointers = [Pubkey::default(); MAX_SHARDS];
         self.created_at = timestamp;
         self.updated_at = timestamp;
-        self.reserved = [0u8; 64];
+        self.directory_pages = 0;
+        self.closed_shards = 0;
+        self.vault_id = owner;
+        self.status = VaultStatus::Active;
+        self.key_epoch = 0;
+        self.master_key_commitment = [0u8; 32];
+        self.frozen_at = 0;
+        self.reserved = [0u8; 17];
+    }
+
+    /// Key the vault master PDA is derived from
+    pub fn vault_seed(&self) -> Pubkey {
+        if self.vault_id == Pubkey::default() {
+            self.owner
+        } else {
+            self.vault_id
+        }
+    }
+
+    /// Move the vault master to a new owner, pinning the vault id first
+    pub fn set_owner(&mut self, new_owner: Pubkey, timestamp: i64) {
+        self.vault_id = self.vault_seed();
+        self.owner = new_owner;
+        self.updated_at = timestamp;
+    }
+
+    /// Check if vault is active
+    pub fn is_active(&self) -> bool {
+        self.status == VaultStatus::Active
+    }
+
+    /// Check if vault is frozen
+    pub fn is_frozen(&self) -> bool {
+        self.status == VaultStatus::Frozen
+    }
+
+    /// Freeze the vault, starting the unfreeze timelock
+    pub fn freeze(&mut self, timestamp: i64) {
+        self.status = VaultStatus::Frozen;
+        self.frozen_at = timestamp;
+        self.updated_at = timestamp;
+    }
+
+    /// Earliest time the vault can be unfrozen
+    pub fn unfreeze_after(&self) -> i64 {
+        self.frozen_at.saturating_add(UNFREEZE_TIMELOCK)
+    }
+
+    /// Check if the unfreeze timelock has elapsed
+    pub fn can_unfreeze(&self, current_timestamp: i64) -> bool {
+        self.is_frozen() && current_timestamp >= self.unfreeze_after()
+    }
+
+    /// Replace the master key commitment and advance the key epoch
+    pub fn rotate_master_key(&mut self, new_commitment: [u8; 32], timestamp: i64) {
+        self.master_key_commitment = new_commitment;
+        self.key_epoch = self.key_epoch.saturating_add(1);
+        self.updated_at = timestamp;
     }
 
     /// Check if a new shard needs to be created
@@ -90,18 +190,55 @@ impl UserVaultMaster {
 
     /// Check if we can create more shards
     pub fn can_create_shard(&self) -> bool {
-        self.total_shards < MAX_SHARDS as u8
+        (self.total_shards as usize) < MAX_TOTAL_SHARDS
+    }
+
+    /// Directory (and index) page that holds a given shard
+    /// Page 0 is the master's inline array and the base index account
+    pub fn page_for_shard(shard_index: u8) -> u8 {
+        (shard_index as usize / MAX_SHARDS) as u8
+    }
+
+    /// Number of shards that can be created with the current directory pages
+    pub fn shard_capacity(&self) -> usize {
+        (self.directory_pages as usize + 1) * MAX_SHARDS
+    }
+
+    /// Check if another directory page can be added
+    pub fn can_add_directory_page(&self) -> bool {
+        (self.directory_pages as usize + 1) < MAX_DIRECTORY_PAGES
+    }
+
+    /// Record a newly created directory page, returning its page index
+    pub fn register_directory_page(&mut self, timestamp: i64) -> Result<u8> {
+        require!(
+            self.can_add_directory_page(),
+            BlockDriveError::MaxShardsReached
+        );
+
+        self.directory_pages = self.directory_pages.saturating_add(1);
+        self.updated_at = timestamp;
+
+        Ok(self.directory_pages)
     }
 
     /// Register a new shard
+    /// Only shards 0-9 are stored inline; callers record overflow shards
+    /// in the matching UserVaultDirectory page
     pub fn register_shard(&mut self, shard_pubkey: Pubkey, timestamp: i64) -> Result<u8> {
         require!(
             self.can_create_shard(),
             BlockDriveError::MaxShardsReached
         );
+        require!(
+            (self.total_shards as usize) < self.shard_capacity(),
+            BlockDriveError::DirectoryPageNotFound
+        );
 
         let shard_index = self.total_shards;
-        self.shard_pointers[shard_index as usize] = shard_pubkey;
+        if (shard_index as usize) < MAX_SHARDS {
+            self.shard_pointers[shard_index as usize] = shard_pubkey;
+        }
         self.total_shards = self.total_shards.saturating_add(1);
         self.active_shard_index = shard_index;
         self.updated_at = timestamp;
@@ -109,6 +246,29 @@ impl UserVaultMaster {
         Ok(shard_index)
     }
 
+    /// Record a closed shard, clearing its inline pointer
+    /// The active shard must be the last one open, so auto-registration never
+    /// targets a closed shard; once all are closed it starts a new one
+    pub fn close_shard(&mut self, shard_index: u8, timestamp: i64) -> Result<()> {
+        require!(
+            shard_index != self.active_shard_index
+                || self.closed_shards.saturating_add(1) >= self.total_shards,
+            BlockDriveError::ActiveShardOpen
+        );
+
+        if (shard_index as usize) < MAX_SHARDS {
+            self.shard_pointers[shard_index as usize] = Pubkey::default();
+        }
+        self.closed_shards = self.closed_shards.saturating_add(1);
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
+    /// Check if every shard ever created has been closed
+    pub fn all_shards_closed(&self) -> bool {
+        self.closed_shards >= self.total_shards
+    }
+
     /// Increment file count when a file is added to any shard
     pub fn add_file(&mut self, file_size: u64, timestamp: i64) {
         self.total_file_count = self.total_file_count.saturating_add(1);
@@ -116,6 +276,12 @@ impl UserVaultMaster {
         self.updated_at = timestamp;
     }
 
+    /// Adjust storage when a file's content is replaced by a new version
+    pub fn resize_file(&mut self, old_size: u64, new_size: u64, timestamp: i64) {
+        self.total_storage = self.total_storage.saturating_sub(old_size).saturating_add(new_size);
+        self.updated_at = timestamp;
+    }
+
     /// Decrement file count when a file is removed
     pub fn remove_file(&mut self, file_size: u64, timestamp: i64) {
         self.total_file_count = self.total_file_count.saturating_sub(1);
@@ -134,9 +300,22 @@ impl UserVaultMaster {
         Ok(())
     }
 
+    /// Make a later, already created shard the active one
+    /// Shards in between (full or closed) are skipped
+    pub fn activate_shard(&mut self, shard_index: u8, timestamp: i64) -> Result<()> {
+        require!(
+            shard_index > self.active_shard_index && shard_index < self.total_shards,
+            BlockDriveError::NoAvailableShard
+        );
+        self.active_shard_index = shard_index;
+        self.updated_at = timestamp;
+        Ok(())
+    }
+
     /// Get the pubkey of the active shard
+    /// Returns None for overflow shards (see UserVaultDirectory)
     pub fn get_active_shard(&self) -> Option<Pubkey> {
-        if self.total_shards == 0 {
+        if self.total_shards == 0 || self.active_shard_index as usize >= MAX_SHARDS {
             return None;
         }
         let pubkey = self.shard_pointers[self.active_shard_index as usize];
@@ -148,8 +327,9 @@ impl UserVaultMaster {
     }
 
     /// Get shard pubkey by index
+    /// Returns None for overflow shards (see UserVaultDirectory)
     pub fn get_shard(&self, index: u8) -> Option<Pubkey> {
-        if index >= self.total_shards {
+        if index >= self.total_shards || index as usize >= MAX_SHARDS {
             return None;
         }
         let pubkey = self.shard_pointers[index as usize];
@@ -173,7 +353,78 @@ impl Default for UserVaultMaster {
             shard_pointers: [Pubkey::default(); MAX_SHARDS],
             created_at: 0,
             updated_at: 0,
-            reserved: [0u8; 64],
+            directory_pages: 0,
+            closed_shards: 0,
+            vault_id: Pubkey::default(),
+            status: VaultStatus::Active,
+            key_epoch: 0,
+            master_key_commitment: [0u8; 32],
+            frozen_at: 0,
+            reserved: [0u8; 17],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_change_rotates_master_commitment() {
+        let old_owner = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+        let mut vault_master = UserVaultMaster::default();
+        vault_master.initialize(255, old_owner, 0);
+        vault_master.rotate_master_key([1u8; 32], 0);
+
+        vault_master.set_owner(new_owner, 100);
+        vault_master.rotate_master_key([2u8; 32], 100);
+
+        assert_eq!(vault_master.owner, new_owner);
+        assert_eq!(vault_master.vault_seed(), old_owner);
+        assert_eq!(vault_master.master_key_commitment, [2u8; 32]);
+        assert_eq!(vault_master.key_epoch, 2);
+    }
+
+    #[test]
+    fn test_activate_shard_skips_forward_only() {
+        let mut vault_master = UserVaultMaster::default();
+        for _ in 0..3 {
+            vault_master.register_shard(Pubkey::new_unique(), 0).unwrap();
         }
+        vault_master.active_shard_index = 0;
+
+        // Shard 1 is full, so registration moves straight to shard 2
+        vault_master.activate_shard(2, 10).unwrap();
+        assert_eq!(vault_master.active_shard_index, 2);
+
+        assert!(vault_master.activate_shard(1, 20).is_err());
+        assert!(vault_master.activate_shard(3, 20).is_err());
+    }
+
+    #[test]
+    fn test_active_shard_closes_last() {
+        let mut vault_master = UserVaultMaster::default();
+        for _ in 0..2 {
+            vault_master.register_shard(Pubkey::new_unique(), 0).unwrap();
+        }
+        assert_eq!(vault_master.active_shard_index, 1);
+
+        assert!(vault_master.close_shard(1, 10).is_err());
+        vault_master.close_shard(0, 10).unwrap();
+        vault_master.close_shard(1, 20).unwrap();
+        assert!(vault_master.all_shards_closed());
+    }
+
+    #[test]
+    fn test_unfreeze_waits_for_timelock() {
+        let mut vault_master = UserVaultMaster::default();
+        vault_master.initialize(255, Pubkey::new_unique(), 0);
+        assert!(!vault_master.can_unfreeze(0));
+
+        vault_master.freeze(100);
+        assert!(vault_master.is_frozen());
+        assert!(!vault_master.can_unfreeze(100 + UNFREEZE_TIMELOCK - 1));
+        assert!(vault_master.can_unfreeze(100 + UNFREEZE_TIMELOCK));
     }
 }
@@ -1,10 +1,12 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 use crate::errors::BlockDriveError;
 
 /// Maximum files per shard
 pub const MAX_FILES_PER_SHARD: usize = 100;
 
 /// ShardStatus enumeration
+/// Stored as a raw u8 in the zero-copy shard account
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ShardStatus {
     #[default]
@@ -13,13 +15,29 @@ pub enum ShardStatus {
     Archived = 2,  // Read-only, migration target
 }
 
+impl ShardStatus {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ShardStatus::Active),
+            1 => Some(ShardStatus::Full),
+            2 => Some(ShardStatus::Archived),
+            _ => None,
+        }
+    }
+}
+
 /// UserVaultShard PDA - The "Storage Unit" that holds file record references
 /// Each shard can hold up to 100 file record pubkeys.
+/// Zero-copy account: load via AccountLoader to avoid (de)serializing 3.2 KB per access.
+/// Fields are ordered so the #[repr(C)] layout has no padding.
 /// Seeds: ["vault_shard", vault_master_pubkey, shard_index (u8)]
-#[account]
+#[account(zero_copy)]
 pub struct UserVaultShard {
-    /// Bump seed for PDA derivation
-    pub bump: u8,
+    /// Shard creation timestamp
+    pub created_at: i64,
+
+    /// Last activity timestamp
+    pub updated_at: i64,
 
     /// Parent vault master pubkey
     pub vault_master: Pubkey,
@@ -27,47 +45,96 @@ pub struct UserVaultShard {
     /// Owner's wallet pubkey (denormalized for efficient queries)
     pub owner: Pubkey,
 
-    /// Index of this shard (0-9)
-    pub shard_index: u8,
-
-    /// Number of files currently in this shard (0-100)
-    pub file_count: u8,
-
-    /// Current status of the shard
-    pub status: ShardStatus,
-
     /// Array of FileRecord PDA pubkeys stored in this shard
     /// Index matches the order files were added
     /// Default Pubkey::default() means slot is empty
     pub file_records: [Pubkey; MAX_FILES_PER_SHARD],
 
-    /// Shard creation timestamp
-    pub created_at: i64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
 
-    /// Last activity timestamp
-    pub updated_at: i64,
+    /// Index of this shard (0-249)
+    pub shard_index: u8,
+
+    /// Number of files currently in this shard (0-100)
+    pub file_count: u8,
+
+    /// Current status of the shard (see ShardStatus)
+    pub status: u8,
 
     /// Reserved for future use
-    pub reserved: [u8; 32],
+    pub reserved: [u8; 28],
 }
 
 impl UserVaultShard {
     /// Account size for rent calculation
     pub const SIZE: usize = 8 +       // discriminator
-        1 +                            // bump
+        8 +                            // created_at
+        8 +                            // updated_at
         32 +                           // vault_master
         32 +                           // owner
+        (32 * MAX_FILES_PER_SHARD) +   // file_records (100 * 32 = 3200)
+        1 +                            // bump
         1 +                            // shard_index
         1 +                            // file_count
         1 +                            // status
-        (32 * MAX_FILES_PER_SHARD) +   // file_records (100 * 32 = 3200)
-        8 +                            // created_at
-        8 +                            // updated_at
-        32;                            // reserved
+        28;                            // reserved
+
+    /// Account size of a shard written with the earlier Borsh layout
+    pub const LEGACY_SIZE: usize = Self::SIZE + 4;
 
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"vault_shard";
 
+    /// Whether an account holds this zero-copy layout
+    /// Shards written with the earlier Borsh layout share the discriminator but
+    /// are 4 bytes longer, so the length tells them apart; migrate_shard_layout
+    /// converts them
+    pub fn has_current_layout(info: &AccountInfo) -> bool {
+        info.data_len() == Self::SIZE
+    }
+
+    /// Whether an account still holds the earlier Borsh layout
+    pub fn has_legacy_layout(info: &AccountInfo) -> bool {
+        info.data_len() == Self::LEGACY_SIZE
+    }
+
+    /// Rewrite the data of a shard from the earlier Borsh layout to this one
+    /// `data` is the whole account, discriminator included. The file records are
+    /// moved in place rather than copied out, keeping the 3.2 KB array off the
+    /// stack; afterwards only the first SIZE bytes are used and the caller
+    /// shrinks the account
+    pub fn rewrite_legacy_layout(data: &mut [u8]) -> Result<()> {
+        require!(
+            data.len() == Self::LEGACY_SIZE && data[..8] == Self::DISCRIMINATOR,
+            ErrorCode::AccountDidNotDeserialize
+        );
+
+        let header = LegacyShardHeader::deserialize(&mut &data[8..])
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+        let records_end = LegacyShardHeader::RECORDS_OFFSET + 32 * MAX_FILES_PER_SHARD;
+        let created_at = i64::from_le_bytes(data[records_end..records_end + 8].try_into().unwrap());
+        let updated_at = i64::from_le_bytes(data[records_end + 8..records_end + 16].try_into().unwrap());
+
+        // file_records sits 12 bytes further in, ahead of the scalar fields
+        let records_offset = 8 + core::mem::offset_of!(UserVaultShard, file_records);
+        data.copy_within(LegacyShardHeader::RECORDS_OFFSET..records_end, records_offset);
+        data[8..records_offset].fill(0);
+        data[records_offset + 32 * MAX_FILES_PER_SHARD..].fill(0);
+
+        let shard: &mut UserVaultShard = bytemuck::from_bytes_mut(&mut data[8..Self::SIZE]);
+        shard.created_at = created_at;
+        shard.updated_at = updated_at;
+        shard.vault_master = header.vault_master;
+        shard.owner = header.owner;
+        shard.bump = header.bump;
+        shard.shard_index = header.shard_index;
+        shard.file_count = header.file_count;
+        shard.status = header.status as u8;
+
+        Ok(())
+    }
+
     /// Initialize a new shard
     pub fn initialize(
         &mut self,
@@ -82,21 +149,26 @@ impl UserVaultShard {
         self.owner = owner;
         self.shard_index = shard_index;
         self.file_count = 0;
-        self.status = ShardStatus::Active;
+        self.status = ShardStatus::Active as u8;
         self.file_records = [Pubkey::default(); MAX_FILES_PER_SHARD];
         self.created_at = timestamp;
         self.updated_at = timestamp;
-        self.reserved = [0u8; 32];
+        self.reserved = [0u8; 28];
+    }
+
+    /// Current shard status
+    pub fn status(&self) -> ShardStatus {
+        ShardStatus::from_u8(self.status).unwrap_or_default()
     }
 
     /// Check if shard can accept new files
     pub fn is_active(&self) -> bool {
-        self.status == ShardStatus::Active
+        self.status() == ShardStatus::Active
     }
 
     /// Check if shard is full
     pub fn is_full(&self) -> bool {
-        self.file_count >= MAX_FILES_PER_SHARD as u8 || self.status == ShardStatus::Full
+        self.file_count >= MAX_FILES_PER_SHARD as u8 || self.status() == ShardStatus::Full
     }
 
     /// Check if shard has space for a new file
@@ -105,6 +177,7 @@ impl UserVaultShard {
     }
 
     /// Add a file record to this shard
+    /// Fills the first empty slot, reusing holes left by removed files
     /// Returns the slot index where the file was added
     pub fn add_file(&mut self, file_record_pubkey: Pubkey, timestamp: i64) -> Result<u8> {
         require!(
@@ -112,21 +185,26 @@ impl UserVaultShard {
             BlockDriveError::ShardFull
         );
 
-        let slot = self.file_count;
+        let slot = self
+            .file_records
+            .iter()
+            .position(|pk| *pk == Pubkey::default())
+            .ok_or(BlockDriveError::ShardFull)? as u8;
         self.file_records[slot as usize] = file_record_pubkey;
         self.file_count = self.file_count.saturating_add(1);
         self.updated_at = timestamp;
 
         // Mark as full if we hit capacity
         if self.file_count >= MAX_FILES_PER_SHARD as u8 {
-            self.status = ShardStatus::Full;
+            self.status = ShardStatus::Full as u8;
         }
 
         Ok(slot)
     }
 
     /// Remove a file record from this shard (mark slot as empty)
-    /// Note: This does NOT compact the array - slot remains empty
+    /// Note: This does NOT compact the array - slot remains empty until
+    /// reused by add_file or closed up by compact
     pub fn remove_file(&mut self, slot_index: u8, timestamp: i64) -> Result<()> {
         require!(
             (slot_index as usize) < MAX_FILES_PER_SHARD,
@@ -142,8 +220,8 @@ impl UserVaultShard {
         self.updated_at = timestamp;
 
         // Reactivate shard if it was full
-        if self.status == ShardStatus::Full {
-            self.status = ShardStatus::Active;
+        if self.status() == ShardStatus::Full {
+            self.status = ShardStatus::Active as u8;
         }
 
         Ok(())
@@ -181,26 +259,118 @@ impl UserVaultShard {
             .collect()
     }
 
+    /// Move all file records into a dense prefix (slots 0..file_count)
+    /// Returns the (from_slot, to_slot) pairs that were moved
+    pub fn compact(&mut self, timestamp: i64) -> Vec<(u8, u8)> {
+        let mut moves = Vec::new();
+        let mut next_slot = 0usize;
+
+        for slot in 0..MAX_FILES_PER_SHARD {
+            let pubkey = self.file_records[slot];
+            if pubkey == Pubkey::default() {
+                continue;
+            }
+            if slot != next_slot {
+                self.file_records[next_slot] = pubkey;
+                self.file_records[slot] = Pubkey::default();
+                moves.push((slot as u8, next_slot as u8));
+            }
+            next_slot += 1;
+        }
+
+        if !moves.is_empty() {
+            self.updated_at = timestamp;
+        }
+        moves
+    }
+
     /// Archive this shard (read-only mode)
     pub fn archive(&mut self, timestamp: i64) {
-        self.status = ShardStatus::Archived;
+        self.status = ShardStatus::Archived as u8;
         self.updated_at = timestamp;
     }
 }
 
-impl Default for UserVaultShard {
-    fn default() -> Self {
-        Self {
-            bump: 0,
-            vault_master: Pubkey::default(),
-            owner: Pubkey::default(),
-            shard_index: 0,
-            file_count: 0,
-            status: ShardStatus::Active,
-            file_records: [Pubkey::default(); MAX_FILES_PER_SHARD],
-            created_at: 0,
-            updated_at: 0,
-            reserved: [0u8; 32],
+/// Leading fields of a shard in the earlier Borsh layout
+/// The file_records array follows them, then created_at, updated_at and 32
+/// reserved bytes
+#[derive(AnchorDeserialize)]
+struct LegacyShardHeader {
+    bump: u8,
+    vault_master: Pubkey,
+    owner: Pubkey,
+    shard_index: u8,
+    file_count: u8,
+    status: ShardStatus,
+}
+
+impl LegacyShardHeader {
+    /// Offset of the file_records array, discriminator included
+    const RECORDS_OFFSET: usize = 8 + 1 + 32 + 32 + 1 + 1 + 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_file_reuses_holes_and_compact() {
+        let mut shard: UserVaultShard = bytemuck::Zeroable::zeroed();
+        let files: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        for file in files.iter() {
+            shard.add_file(*file, 0).unwrap();
         }
+
+        // Removing a file leaves a hole that the next add fills
+        shard.remove_file(1, 0).unwrap();
+        let replacement = Pubkey::new_unique();
+        assert_eq!(shard.add_file(replacement, 0).unwrap(), 1);
+        assert_eq!(shard.get_file(3), Some(files[3]));
+
+        // Compaction packs the remaining files into slots 0..file_count
+        shard.remove_file(0, 0).unwrap();
+        shard.remove_file(2, 0).unwrap();
+        assert_eq!(shard.compact(0), vec![(1, 0), (3, 1)]);
+        assert_eq!(shard.get_file(0), Some(replacement));
+        assert_eq!(shard.get_file(1), Some(files[3]));
+        assert_eq!(shard.get_file(3), None);
+        assert_eq!(shard.file_count, 2);
+    }
+
+    #[test]
+    fn test_rewrite_legacy_layout() {
+        let vault_master = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let file = Pubkey::new_unique();
+
+        let mut legacy = UserVaultShard::DISCRIMINATOR.to_vec();
+        legacy.push(254); // bump
+        legacy.extend_from_slice(vault_master.as_ref());
+        legacy.extend_from_slice(owner.as_ref());
+        legacy.extend_from_slice(&[3, 1, ShardStatus::Active as u8]);
+        for slot in 0..MAX_FILES_PER_SHARD {
+            let record = if slot == 2 { file } else { Pubkey::default() };
+            legacy.extend_from_slice(record.as_ref());
+        }
+        legacy.extend_from_slice(&100i64.to_le_bytes());
+        legacy.extend_from_slice(&200i64.to_le_bytes());
+        legacy.extend_from_slice(&[0u8; 32]);
+        assert_eq!(legacy.len(), UserVaultShard::LEGACY_SIZE);
+
+        // Account data is 8-byte aligned on chain
+        let mut words = vec![0u64; UserVaultShard::LEGACY_SIZE.div_ceil(8)];
+        let data = &mut bytemuck::cast_slice_mut::<u64, u8>(&mut words)[..UserVaultShard::LEGACY_SIZE];
+        data.copy_from_slice(&legacy);
+        UserVaultShard::rewrite_legacy_layout(data).unwrap();
+
+        let shard: &UserVaultShard = bytemuck::from_bytes(&data[8..UserVaultShard::SIZE]);
+        assert_eq!(shard.vault_master, vault_master);
+        assert_eq!(shard.owner, owner);
+        assert_eq!((shard.bump, shard.shard_index, shard.file_count), (254, 3, 1));
+        assert!(shard.has_capacity());
+        assert_eq!((shard.created_at, shard.updated_at), (100, 200));
+        assert_eq!(shard.get_all_files(), vec![file]);
+        assert_eq!(shard.find_file_slot(&file), Some(2));
+        assert_eq!(shard.reserved, [0u8; 28]);
     }
 }
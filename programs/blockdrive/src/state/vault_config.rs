@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+/// Upper bound for the cleanup tip paid to crankers (lamports)
+/// Keeps the bulk of reclaimed rent with the account's original payer
+pub const MAX_CLEANUP_TIP: u64 = 100_000;
+
+/// VaultConfig PDA - global program configuration set by the program authority
+/// Holds fees, limits and the emergency pause switch checked by user instructions.
+/// Seeds: ["config"]
+#[account]
+pub struct VaultConfig {
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Program authority allowed to update the config
+    pub authority: Pubkey,
+
+    /// Fee collector wallet
+    pub fee_collector: Pubkey,
+
+    /// File registration fee (lamports)
+    pub file_registration_fee: u64,
+
+    /// Delegation creation fee (lamports)
+    pub delegation_fee: u64,
+
+    /// Maximum file size allowed in bytes (0 = unlimited)
+    pub max_file_size: u64,
+
+    /// Maximum files per vault (0 = unlimited)
+    pub max_files_per_vault: u64,
+
+    /// Is program paused
+    pub is_paused: bool,
+
+    /// Config creation timestamp
+    pub created_at: i64,
+
+    /// Last update timestamp
+    pub updated_at: i64,
+
+    /// Lamports paid to whoever closes an expired delegation or session
+    /// Paid out of this account's balance above rent (see fund_cleanup_tips);
+    /// the closed account's rent goes back to its original payer in full
+    pub cleanup_tip: u64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 56],
+}
+
+impl VaultConfig {
+    /// Account size for rent calculation
+    pub const SIZE: usize = 8 +   // discriminator
+        1 +   // bump
+        32 +  // authority
+        32 +  // fee_collector
+        8 +   // file_registration_fee
+        8 +   // delegation_fee
+        8 +   // max_file_size
+        8 +   // max_files_per_vault
+        1 +   // is_paused
+        8 +   // created_at
+        8 +   // updated_at
+        8 +   // cleanup_tip
+        56;   // reserved
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"config";
+
+    /// Check if a file of the given size is within the configured limit
+    pub fn is_file_size_allowed(&self, file_size: u64) -> bool {
+        self.max_file_size == 0 || file_size <= self.max_file_size
+    }
+
+    /// Check if a vault holding `current_file_count` files can accept another
+    pub fn can_add_file(&self, current_file_count: u64) -> bool {
+        self.max_files_per_vault == 0 || current_file_count < self.max_files_per_vault
+    }
+}
+
+impl Default for VaultConfig {
+    fn default() -> Self {
+        Self {
+            bump: 0,
+            authority: Pubkey::default(),
+            fee_collector: Pubkey::default(),
+            file_registration_fee: 0,
+            delegation_fee: 0,
+            max_file_size: 0,
+            max_files_per_vault: 0,
+            is_paused: false,
+            created_at: 0,
+            updated_at: 0,
+            cleanup_tip: 0,
+            reserved: [0u8; 56],
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+use crate::errors::BlockDriveError;
+use crate::state::MAX_SHARDS;
+
+/// Shard pointers held by each directory page (same as the master's inline array)
+pub const SHARDS_PER_DIRECTORY_PAGE: usize = MAX_SHARDS;
+
+/// UserVaultDirectory PDA - Overflow "extension page" of the Vault Master
+/// The master stores pointers for shards 0-9 inline; page N (N >= 1) stores
+/// pointers for shards N*10 .. N*10+9. Each page has a matching index page.
+/// Seeds: ["vault_directory", vault_master_pubkey, page_index (u8)]
+#[account]
+pub struct UserVaultDirectory {
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Parent vault master pubkey
+    pub vault_master: Pubkey,
+
+    /// Owner's wallet pubkey (denormalized for efficient queries)
+    pub owner: Pubkey,
+
+    /// Page number (1+, page 0 is the master itself)
+    pub page_index: u8,
+
+    /// Number of shards registered in this page (0-10)
+    pub shard_count: u8,
+
+    /// Pubkeys of the UserVaultShard PDAs covered by this page
+    /// Slot i holds shard page_index * 10 + i
+    /// Default Pubkey::default() means shard not yet created
+    pub shard_pointers: [Pubkey; SHARDS_PER_DIRECTORY_PAGE],
+
+    /// Page creation timestamp
+    pub created_at: i64,
+
+    /// Last activity timestamp
+    pub updated_at: i64,
+
+    /// Reserved for future use
+    pub reserved: [u8; 32],
+}
+
+impl UserVaultDirectory {
+    /// Account size for rent calculation
+    pub const SIZE: usize = 8 +       // discriminator
+        1 +                            // bump
+        32 +                           // vault_master
+        32 +                           // owner
+        1 +                            // page_index
+        1 +                            // shard_count
+        (32 * SHARDS_PER_DIRECTORY_PAGE) + // shard_pointers (10 * 32 = 320)
+        8 +                            // created_at
+        8 +                            // updated_at
+        32;                            // reserved
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"vault_directory";
+
+    /// Initialize a new directory page
+    pub fn initialize(
+        &mut self,
+        bump: u8,
+        vault_master: Pubkey,
+        owner: Pubkey,
+        page_index: u8,
+        timestamp: i64,
+    ) {
+        self.bump = bump;
+        self.vault_master = vault_master;
+        self.owner = owner;
+        self.page_index = page_index;
+        self.shard_count = 0;
+        self.shard_pointers = [Pubkey::default(); SHARDS_PER_DIRECTORY_PAGE];
+        self.created_at = timestamp;
+        self.updated_at = timestamp;
+        self.reserved = [0u8; 32];
+    }
+
+    /// Check if this page covers the given absolute shard index
+    pub fn covers_shard(&self, shard_index: u8) -> bool {
+        shard_index as usize / SHARDS_PER_DIRECTORY_PAGE == self.page_index as usize
+    }
+
+    /// Record the pointer for a newly created shard
+    pub fn register_shard(
+        &mut self,
+        shard_index: u8,
+        shard_pubkey: Pubkey,
+        timestamp: i64,
+    ) -> Result<()> {
+        require!(
+            self.covers_shard(shard_index),
+            BlockDriveError::InvalidDirectoryPage
+        );
+
+        let slot = shard_index as usize % SHARDS_PER_DIRECTORY_PAGE;
+        self.shard_pointers[slot] = shard_pubkey;
+        self.shard_count = self.shard_count.saturating_add(1);
+        self.updated_at = timestamp;
+
+        Ok(())
+    }
+
+    /// Clear the pointer of a closed shard
+    pub fn unregister_shard(&mut self, shard_index: u8, timestamp: i64) -> Result<()> {
+        require!(
+            self.covers_shard(shard_index),
+            BlockDriveError::InvalidDirectoryPage
+        );
+
+        let slot = shard_index as usize % SHARDS_PER_DIRECTORY_PAGE;
+        self.shard_pointers[slot] = Pubkey::default();
+        self.shard_count = self.shard_count.saturating_sub(1);
+        self.updated_at = timestamp;
+
+        Ok(())
+    }
+
+    /// Get shard pubkey by absolute shard index
+    pub fn get_shard(&self, shard_index: u8) -> Option<Pubkey> {
+        if !self.covers_shard(shard_index) {
+            return None;
+        }
+        let pubkey = self.shard_pointers[shard_index as usize % SHARDS_PER_DIRECTORY_PAGE];
+        if pubkey == Pubkey::default() {
+            None
+        } else {
+            Some(pubkey)
+        }
+    }
+}
+
+impl Default for UserVaultDirectory {
+    fn default() -> Self {
+        Self {
+            bump: 0,
+            vault_master: Pubkey::default(),
+            owner: Pubkey::default(),
+            page_index: 0,
+            shard_count: 0,
+            shard_pointers: [Pubkey::default(); SHARDS_PER_DIRECTORY_PAGE],
+            created_at: 0,
+            updated_at: 0,
+            reserved: [0u8; 32],
+        }
+    }
+}
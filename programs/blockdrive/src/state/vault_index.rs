@@ -1,16 +1,19 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 use crate::errors::BlockDriveError;
+use crate::state::UserVaultMaster;
 
-/// Maximum entries in the index (supports 1000 files)
-/// Each entry is 17 bytes (16 byte file_id + 1 byte shard_index)
+/// Maximum entries per index page (10 shards * 100 files)
+/// Each entry is 18 bytes (16 byte file_id + 1 byte shard_index + 1 byte slot_index)
 pub const MAX_INDEX_ENTRIES: usize = 1000;
 
 /// Single index entry mapping file_id to shard location
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq)]
+#[zero_copy]
+#[derive(Default, PartialEq)]
 pub struct IndexEntry {
     /// Unique file identifier (UUID bytes)
     pub file_id: [u8; 16],
-    /// Shard index where this file lives (0-9)
+    /// Shard index where this file lives (absolute, 0-249)
     pub shard_index: u8,
     /// Slot index within the shard (0-99)
     pub slot_index: u8,
@@ -32,13 +35,32 @@ impl IndexEntry {
     }
 }
 
+/// Resolved location of a file, returned by the query_file_location view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileLocation {
+    /// Shard index where the file lives
+    pub shard_index: u8,
+    /// Slot index within the shard
+    pub slot_index: u8,
+    /// FileRecord PDA pubkey
+    pub file_record: Pubkey,
+}
+
 /// UserVaultIndex PDA - The "Lookup Table" for fast file location queries
-/// Maps file_id -> (shard_index, slot_index) for O(1) lookups
-/// Seeds: ["vault_index", vault_master_pubkey]
-#[account]
+/// Maps file_id -> (shard_index, slot_index) via binary search
+/// The index is paginated: page N holds entries for shards N*10 .. N*10+9
+/// Zero-copy account: entries live in a fixed array of which the first
+/// `entry_count` are in use, kept sorted by file_id.
+/// Fields are ordered so the #[repr(C)] layout has no padding.
+/// Seeds: ["vault_index", vault_master_pubkey] (page 0)
+///        ["vault_index", vault_master_pubkey, page_index (u8)] (page 1+)
+#[account(zero_copy)]
 pub struct UserVaultIndex {
-    /// Bump seed for PDA derivation
-    pub bump: u8,
+    /// Index creation timestamp
+    pub created_at: i64,
+
+    /// Last activity timestamp
+    pub updated_at: i64,
 
     /// Parent vault master pubkey
     pub vault_master: Pubkey,
@@ -49,45 +71,100 @@ pub struct UserVaultIndex {
     /// Number of entries currently in the index
     pub entry_count: u16,
 
-    /// Index creation timestamp
-    pub created_at: i64,
-
-    /// Last activity timestamp
-    pub updated_at: i64,
+    /// Bump seed for PDA derivation
+    pub bump: u8,
 
-    /// Dynamic array of index entries
-    /// Note: In production, consider using a more efficient data structure
-    /// or off-chain indexing for larger file counts
-    pub entries: Vec<IndexEntry>,
+    /// Page number of this index account (0 = base index)
+    pub page_index: u8,
 
     /// Reserved for future use
-    pub reserved: [u8; 32],
+    pub reserved: [u8; 28],
+
+    /// Fixed array of index entries; the first `entry_count` are in use,
+    /// sorted ascending by file_id
+    pub entries: [IndexEntry; MAX_INDEX_ENTRIES],
 }
 
 impl UserVaultIndex {
-    /// Base account size (without dynamic entries)
+    /// Account size without the entries array
     pub const BASE_SIZE: usize = 8 +   // discriminator
-        1 +                             // bump
+        8 +                             // created_at
+        8 +                             // updated_at
         32 +                            // vault_master
         32 +                            // owner
         2 +                             // entry_count
-        8 +                             // created_at
-        8 +                             // updated_at
-        4 +                             // vec length prefix
-        32;                             // reserved
+        1 +                             // bump
+        1 +                             // page_index
+        28;                             // reserved
 
-    /// Maximum account size with all entries
+    /// Full account size including all entries
     pub const MAX_SIZE: usize = Self::BASE_SIZE + (IndexEntry::SIZE * MAX_INDEX_ENTRIES);
 
+    /// Account size of a page written with the earlier Borsh layout
+    pub const LEGACY_SIZE: usize = Self::MAX_SIZE + 7;
+
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"vault_index";
 
-    /// Initialize a new index
+    /// Whether an account holds this zero-copy layout
+    /// Pages written with the earlier Borsh layout share the discriminator but
+    /// are 7 bytes longer, so the length tells them apart; migrate_index_layout
+    /// converts them
+    pub fn has_current_layout(info: &AccountInfo) -> bool {
+        info.data_len() == Self::MAX_SIZE
+    }
+
+    /// Whether an account still holds the earlier Borsh layout
+    pub fn has_legacy_layout(info: &AccountInfo) -> bool {
+        info.data_len() == Self::LEGACY_SIZE
+    }
+
+    /// Rewrite the data of an index from the earlier Borsh layout to this one
+    /// `data` is the whole account, discriminator included. The old index kept
+    /// entries in insertion order, so they are sorted for binary search. Entries
+    /// are moved in place; afterwards only the first MAX_SIZE bytes are used and
+    /// the caller shrinks the account
+    pub fn rewrite_legacy_layout(data: &mut [u8]) -> Result<()> {
+        require!(
+            data.len() == Self::LEGACY_SIZE && data[..8] == Self::DISCRIMINATOR,
+            ErrorCode::AccountDidNotDeserialize
+        );
+
+        let header = LegacyIndexHeader::deserialize(&mut &data[8..])
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+        let entry_count = header.entries_len as usize;
+        require!(
+            entry_count <= MAX_INDEX_ENTRIES,
+            ErrorCode::AccountDidNotDeserialize
+        );
+
+        // Paginated pages store page_index right after the entries; in the
+        // original single index that byte is reserved and zero, i.e. page 0
+        let entries_len = entry_count * IndexEntry::SIZE;
+        let page_index = data[LegacyIndexHeader::ENTRIES_OFFSET + entries_len];
+        data.copy_within(
+            LegacyIndexHeader::ENTRIES_OFFSET..LegacyIndexHeader::ENTRIES_OFFSET + entries_len,
+            Self::BASE_SIZE,
+        );
+        data[8..Self::BASE_SIZE].fill(0);
+        data[Self::BASE_SIZE + entries_len..].fill(0);
+
+        let index: &mut UserVaultIndex = bytemuck::from_bytes_mut(&mut data[8..Self::MAX_SIZE]);
+        index.initialize(header.bump, header.vault_master, header.owner, page_index, header.created_at);
+        index.updated_at = header.updated_at;
+        index.entry_count = entry_count as u16;
+        index.entries[..entry_count].sort_unstable_by_key(|entry| entry.file_id);
+
+        Ok(())
+    }
+
+    /// Initialize a new index page
     pub fn initialize(
         &mut self,
         bump: u8,
         vault_master: Pubkey,
         owner: Pubkey,
+        page_index: u8,
         timestamp: i64,
     ) {
         self.bump = bump;
@@ -96,8 +173,30 @@ impl UserVaultIndex {
         self.entry_count = 0;
         self.created_at = timestamp;
         self.updated_at = timestamp;
-        self.entries = Vec::with_capacity(100); // Start with reasonable capacity
-        self.reserved = [0u8; 32];
+        self.page_index = page_index;
+        self.reserved = [0u8; 28];
+    }
+
+    /// Check if this page holds entries for the given shard
+    pub fn covers_shard(&self, shard_index: u8) -> bool {
+        UserVaultMaster::page_for_shard(shard_index) == self.page_index
+    }
+
+    /// Entries currently in use
+    pub fn active_entries(&self) -> &[IndexEntry] {
+        &self.entries[..self.entry_count as usize]
+    }
+
+    /// Binary search the active entries for a file_id
+    /// Ok(position) if present, Err(insertion point) otherwise
+    fn search(&self, file_id: &[u8; 16]) -> core::result::Result<usize, usize> {
+        self.active_entries()
+            .binary_search_by(|e| e.file_id.cmp(file_id))
+    }
+
+    /// Position of a file_id within the active entries
+    fn position(&self, file_id: &[u8; 16]) -> Option<usize> {
+        self.search(file_id).ok()
     }
 
     /// Add a file to the index
@@ -109,18 +208,20 @@ impl UserVaultIndex {
         timestamp: i64,
     ) -> Result<()> {
         require!(
-            (self.entry_count as usize) < MAX_INDEX_ENTRIES,
+            self.has_capacity(),
             BlockDriveError::IndexFull
         );
 
-        // Check for duplicate file_id
-        require!(
-            self.find_entry(&file_id).is_none(),
-            BlockDriveError::DuplicateFileId
-        );
+        // Duplicate check and insertion point in one search
+        let index = match self.search(&file_id) {
+            Ok(_) => return err!(BlockDriveError::DuplicateFileId),
+            Err(index) => index,
+        };
 
-        let entry = IndexEntry::new(file_id, shard_index, slot_index);
-        self.entries.push(entry);
+        // Shift the tail right by one to keep the entries sorted
+        let count = self.entry_count as usize;
+        self.entries.copy_within(index..count, index + 1);
+        self.entries[index] = IndexEntry::new(file_id, shard_index, slot_index);
         self.entry_count = self.entry_count.saturating_add(1);
         self.updated_at = timestamp;
 
@@ -129,30 +230,27 @@ impl UserVaultIndex {
 
     /// Remove a file from the index
     pub fn remove_entry(&mut self, file_id: &[u8; 16], timestamp: i64) -> Result<()> {
-        let position = self.entries.iter().position(|e| &e.file_id == file_id);
-
-        match position {
-            Some(index) => {
-                self.entries.swap_remove(index); // O(1) removal
-                self.entry_count = self.entry_count.saturating_sub(1);
-                self.updated_at = timestamp;
-                Ok(())
-            }
-            None => Err(BlockDriveError::FileNotFound.into()),
-        }
+        let index = self.position(file_id).ok_or(BlockDriveError::FileNotFound)?;
+
+        // Shift the tail left by one to keep the entries sorted
+        let count = self.entry_count as usize;
+        self.entries.copy_within(index + 1..count, index);
+        self.entries[count - 1] = IndexEntry::default();
+        self.entry_count = self.entry_count.saturating_sub(1);
+        self.updated_at = timestamp;
+
+        Ok(())
     }
 
     /// Find entry by file_id - returns (shard_index, slot_index)
     pub fn find_entry(&self, file_id: &[u8; 16]) -> Option<(u8, u8)> {
-        self.entries
-            .iter()
-            .find(|e| &e.file_id == file_id)
-            .map(|e| (e.shard_index, e.slot_index))
+        self.position(file_id)
+            .map(|index| (self.entries[index].shard_index, self.entries[index].slot_index))
     }
 
     /// Get all entries for a specific shard
     pub fn get_entries_for_shard(&self, shard_index: u8) -> Vec<&IndexEntry> {
-        self.entries
+        self.active_entries()
             .iter()
             .filter(|e| e.shard_index == shard_index)
             .collect()
@@ -166,12 +264,9 @@ impl UserVaultIndex {
         new_slot_index: u8,
         timestamp: i64,
     ) -> Result<()> {
-        let entry = self
-            .entries
-            .iter_mut()
-            .find(|e| &e.file_id == file_id)
-            .ok_or(BlockDriveError::FileNotFound)?;
+        let index = self.position(file_id).ok_or(BlockDriveError::FileNotFound)?;
 
+        let entry = &mut self.entries[index];
         entry.shard_index = new_shard_index;
         entry.slot_index = new_slot_index;
         self.updated_at = timestamp;
@@ -186,24 +281,107 @@ impl UserVaultIndex {
 
     /// Get count of files in a specific shard
     pub fn count_files_in_shard(&self, shard_index: u8) -> u16 {
-        self.entries
+        self.active_entries()
             .iter()
             .filter(|e| e.shard_index == shard_index)
             .count() as u16
     }
 }
 
-impl Default for UserVaultIndex {
-    fn default() -> Self {
-        Self {
-            bump: 0,
-            vault_master: Pubkey::default(),
-            owner: Pubkey::default(),
-            entry_count: 0,
-            created_at: 0,
-            updated_at: 0,
-            entries: Vec::new(),
-            reserved: [0u8; 32],
+/// Leading fields of an index in the earlier Borsh layout
+/// The entries follow as a Borsh Vec, then page_index and 31 reserved bytes
+#[derive(AnchorDeserialize)]
+struct LegacyIndexHeader {
+    bump: u8,
+    vault_master: Pubkey,
+    owner: Pubkey,
+    _entry_count: u16,
+    created_at: i64,
+    updated_at: i64,
+    entries_len: u32,
+}
+
+impl LegacyIndexHeader {
+    /// Offset of the first entry, discriminator included
+    const ENTRIES_OFFSET: usize = 8 + 1 + 32 + 32 + 2 + 8 + 8 + 4;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_id(n: u8) -> [u8; 16] {
+        let mut id = [0u8; 16];
+        id[0] = n;
+        id
+    }
+
+    #[test]
+    fn test_entries_stay_sorted() {
+        let mut index: Box<UserVaultIndex> = Box::new(bytemuck::Zeroable::zeroed());
+
+        for n in [5u8, 1, 9, 3, 7] {
+            index.add_entry(file_id(n), 0, n, 0).unwrap();
         }
+        assert!(index.add_entry(file_id(3), 0, 0, 0).is_err());
+
+        let ids: Vec<u8> = index.active_entries().iter().map(|e| e.file_id[0]).collect();
+        assert_eq!(ids, vec![1, 3, 5, 7, 9]);
+        assert_eq!(index.find_entry(&file_id(7)), Some((0, 7)));
+
+        index.remove_entry(&file_id(3), 0).unwrap();
+        let ids: Vec<u8> = index.active_entries().iter().map(|e| e.file_id[0]).collect();
+        assert_eq!(ids, vec![1, 5, 7, 9]);
+        assert_eq!(index.find_entry(&file_id(3)), None);
+        assert!(index.entries[4].is_empty());
+    }
+
+    #[test]
+    fn test_rewrite_legacy_layout_sorts_entries() {
+        let vault_master = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let mut legacy = UserVaultIndex::DISCRIMINATOR.to_vec();
+        legacy.push(253); // bump
+        legacy.extend_from_slice(vault_master.as_ref());
+        legacy.extend_from_slice(owner.as_ref());
+        legacy.extend_from_slice(&3u16.to_le_bytes());
+        legacy.extend_from_slice(&100i64.to_le_bytes());
+        legacy.extend_from_slice(&200i64.to_le_bytes());
+        legacy.extend_from_slice(&3u32.to_le_bytes());
+        // Insertion order, as the old push/swap_remove index left them
+        for (n, shard, slot) in [(9u8, 0u8, 0u8), (2, 1, 4), (5, 0, 1)] {
+            legacy.extend_from_slice(&file_id(n));
+            legacy.extend_from_slice(&[shard, slot]);
+        }
+        legacy.resize(UserVaultIndex::LEGACY_SIZE, 0);
+
+        // Account data is 8-byte aligned on chain
+        let mut words = vec![0u64; UserVaultIndex::LEGACY_SIZE.div_ceil(8)];
+        let data = &mut bytemuck::cast_slice_mut::<u64, u8>(&mut words)[..UserVaultIndex::LEGACY_SIZE];
+
+        // A paginated page keeps its page_index after the entries
+        data.copy_from_slice(&legacy);
+        data[LegacyIndexHeader::ENTRIES_OFFSET + 3 * IndexEntry::SIZE] = 2;
+        UserVaultIndex::rewrite_legacy_layout(data).unwrap();
+        let index: &UserVaultIndex = bytemuck::from_bytes(&data[8..UserVaultIndex::MAX_SIZE]);
+        assert_eq!(index.page_index, 2);
+
+        // The original single index has a zeroed reserved byte there
+        let data = &mut bytemuck::cast_slice_mut::<u64, u8>(&mut words)[..UserVaultIndex::LEGACY_SIZE];
+        data.copy_from_slice(&legacy);
+        UserVaultIndex::rewrite_legacy_layout(data).unwrap();
+
+        let index: &UserVaultIndex = bytemuck::from_bytes(&data[8..UserVaultIndex::MAX_SIZE]);
+        assert_eq!(index.vault_master, vault_master);
+        assert_eq!(index.owner, owner);
+        assert_eq!((index.bump, index.page_index, index.entry_count), (253, 0, 3));
+        assert_eq!((index.created_at, index.updated_at), (100, 200));
+
+        let ids: Vec<u8> = index.active_entries().iter().map(|e| e.file_id[0]).collect();
+        assert_eq!(ids, vec![2, 5, 9]);
+        assert_eq!(index.find_entry(&file_id(2)), Some((1, 4)));
+        assert_eq!(index.find_entry(&file_id(9)), Some((0, 0)));
+        assert!(index.entries[3].is_empty());
     }
 }
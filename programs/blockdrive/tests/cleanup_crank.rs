@@ -0,0 +1,165 @@
+//! close_expired_delegation / close_expired_session end to end
+
+mod common;
+
+use anchor_lang::prelude::*;
+use solana_program_test::tokio;
+use solana_sdk::{signature::Keypair, signer::Signer as _};
+
+use blockdrive::state::{Delegation, FileRecord, PermissionLevel, VaultConfig};
+use common::*;
+
+const TIP: u64 = 5_000;
+
+struct Fixture {
+    config: Pubkey,
+    file_record: Pubkey,
+    delegation: Pubkey,
+    grantor: Pubkey,
+}
+
+fn setup(pool: u64, expires_at: i64) -> (solana_program_test::ProgramTest, Fixture) {
+    let mut program_test = program_test();
+
+    let config = add_config(
+        &mut program_test,
+        VaultConfig {
+            cleanup_tip: TIP,
+            ..Default::default()
+        },
+        pool,
+    );
+
+    let vault = Pubkey::new_unique();
+    let grantor = Pubkey::new_unique();
+    let grantee = Pubkey::new_unique();
+    let file_id = [7u8; 16];
+
+    let (file_record, file_bump) = Pubkey::find_program_address(
+        &[FileRecord::SEED_PREFIX, vault.as_ref(), &file_id],
+        &blockdrive::ID,
+    );
+    add_program_account(
+        &mut program_test,
+        file_record,
+        &FileRecord {
+            bump: file_bump,
+            vault,
+            owner: grantor,
+            file_id,
+            is_shared: true,
+            delegation_count: 1,
+            ..Default::default()
+        },
+        FileRecord::SIZE,
+        rent_exempt(FileRecord::SIZE),
+    );
+
+    let (delegation, delegation_bump) = Pubkey::find_program_address(
+        &[Delegation::SEED_PREFIX, file_record.as_ref(), grantee.as_ref()],
+        &blockdrive::ID,
+    );
+    add_program_account(
+        &mut program_test,
+        delegation,
+        &Delegation {
+            bump: delegation_bump,
+            file_record,
+            grantor,
+            grantee,
+            permission_level: PermissionLevel::View,
+            expires_at,
+            created_at: 1,
+            is_active: true,
+            ..Default::default()
+        },
+        Delegation::SIZE,
+        rent_exempt(Delegation::SIZE),
+    );
+
+    (
+        program_test,
+        Fixture {
+            config,
+            file_record,
+            delegation,
+            grantor,
+        },
+    )
+}
+
+fn close_expired_delegation(fixture: &Fixture, cranker: &Keypair) -> solana_sdk::instruction::Instruction {
+    instruction(
+        blockdrive::accounts::CloseExpiredDelegation {
+            delegation: fixture.delegation,
+            file_record: fixture.file_record,
+            grantor: fixture.grantor,
+            config: fixture.config,
+            cranker: cranker.pubkey(),
+        },
+        blockdrive::instruction::CloseExpiredDelegation {},
+    )
+}
+
+#[tokio::test]
+async fn test_crank_refunds_full_rent_and_tips_from_pool() {
+    let (mut program_test, fixture) = setup(3 * TIP, 2);
+    let cranker = Keypair::new();
+    add_wallet(&mut program_test, cranker.pubkey(), 1_000_000);
+    let mut context = program_test.start_with_context().await;
+
+    send(&mut context, &[close_expired_delegation(&fixture, &cranker)], &[&cranker])
+        .await
+        .unwrap();
+
+    // The grantor gets every lamport of rent back, the tip comes from the pool
+    assert_eq!(lamports(&mut context, fixture.delegation).await, 0);
+    assert_eq!(
+        lamports(&mut context, fixture.grantor).await,
+        rent_exempt(Delegation::SIZE)
+    );
+    assert_eq!(lamports(&mut context, cranker.pubkey()).await, 1_000_000 + TIP);
+    assert_eq!(
+        lamports(&mut context, fixture.config).await,
+        rent_exempt(VaultConfig::SIZE) + 2 * TIP
+    );
+
+    let record: FileRecord = fetch(&mut context, fixture.file_record).await.unwrap();
+    assert_eq!(record.delegation_count, 0);
+    assert!(!record.is_shared);
+}
+
+#[tokio::test]
+async fn test_crank_with_empty_pool_still_closes() {
+    let (mut program_test, fixture) = setup(0, 2);
+    let cranker = Keypair::new();
+    add_wallet(&mut program_test, cranker.pubkey(), 1_000_000);
+    let mut context = program_test.start_with_context().await;
+
+    send(&mut context, &[close_expired_delegation(&fixture, &cranker)], &[&cranker])
+        .await
+        .unwrap();
+
+    assert_eq!(lamports(&mut context, fixture.delegation).await, 0);
+    assert_eq!(lamports(&mut context, cranker.pubkey()).await, 1_000_000);
+    assert_eq!(
+        lamports(&mut context, fixture.config).await,
+        rent_exempt(VaultConfig::SIZE)
+    );
+}
+
+#[tokio::test]
+async fn test_crank_rejects_live_delegation() {
+    // No expiry
+    let (mut program_test, fixture) = setup(3 * TIP, 0);
+    let cranker = Keypair::new();
+    add_wallet(&mut program_test, cranker.pubkey(), 1_000_000);
+    let mut context = program_test.start_with_context().await;
+
+    assert!(
+        send(&mut context, &[close_expired_delegation(&fixture, &cranker)], &[&cranker])
+            .await
+            .is_err()
+    );
+    assert!(fetch::<Delegation>(&mut context, fixture.delegation).await.is_some());
+}
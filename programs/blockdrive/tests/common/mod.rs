@@ -0,0 +1,213 @@
+//! Shared harness for the instruction-level tests
+//! The program runs natively inside solana-program-test; accounts are seeded
+//! directly instead of going through initialize_config, which needs the
+//! program's upgrade authority
+
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account as SolanaAccount, instruction::Instruction, signature::Keypair,
+    signer::Signer as _, transaction::Transaction,
+};
+
+use anchor_lang::Discriminator;
+use blockdrive::instructions::sharding::{derive_vault_index_pda, derive_vault_shard_pda};
+use blockdrive::state::{UserVaultIndex, UserVaultShard, VaultConfig, MAX_FILES_PER_SHARD};
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // The Anchor entrypoint wants the account slice to live as long as the
+    // accounts themselves
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    blockdrive::entry(program_id, accounts, data)
+}
+
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "blockdrive",
+        blockdrive::ID,
+        processor!(process_instruction),
+    );
+    program_test.prefer_bpf(false);
+    program_test
+}
+
+/// Rent-exempt balance for `len` bytes
+pub fn rent_exempt(len: usize) -> u64 {
+    Rent::default().minimum_balance(len)
+}
+
+/// Seed a program-owned Anchor account holding `value`, padded to `space`
+pub fn add_program_account<T: AccountSerialize>(
+    program_test: &mut ProgramTest,
+    address: Pubkey,
+    value: &T,
+    space: usize,
+    lamports: u64,
+) {
+    let mut data = Vec::with_capacity(space);
+    value.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    program_test.add_account(
+        address,
+        SolanaAccount {
+            lamports,
+            data,
+            owner: blockdrive::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+/// Seed a rent-exempt program-owned account holding raw `data`
+pub fn add_raw_account(program_test: &mut ProgramTest, address: Pubkey, data: Vec<u8>) {
+    program_test.add_account(
+        address,
+        SolanaAccount {
+            lamports: rent_exempt(data.len()),
+            data,
+            owner: blockdrive::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+/// A shard as the Borsh layout stored it, with `records` in the leading slots
+pub fn legacy_shard(vault_master: Pubkey, owner: Pubkey, shard_index: u8, records: &[Pubkey]) -> Vec<u8> {
+    let bump = derive_vault_shard_pda(&vault_master, shard_index, &blockdrive::ID).1;
+    let mut data = UserVaultShard::DISCRIMINATOR.to_vec();
+    data.push(bump);
+    data.extend_from_slice(vault_master.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&[shard_index, records.len() as u8, 0]);
+    for slot in 0..MAX_FILES_PER_SHARD {
+        let record = records.get(slot).copied().unwrap_or_default();
+        data.extend_from_slice(record.as_ref());
+    }
+    data.extend_from_slice(&1i64.to_le_bytes());
+    data.extend_from_slice(&2i64.to_le_bytes());
+    data.resize(UserVaultShard::LEGACY_SIZE, 0);
+    data
+}
+
+/// An index as the Borsh layout stored it, entries in insertion order
+pub fn legacy_index(vault_master: Pubkey, owner: Pubkey, entries: &[([u8; 16], u8, u8)]) -> Vec<u8> {
+    let bump = derive_vault_index_pda(&vault_master, &blockdrive::ID).1;
+    let mut data = UserVaultIndex::DISCRIMINATOR.to_vec();
+    data.push(bump);
+    data.extend_from_slice(vault_master.as_ref());
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    data.extend_from_slice(&1i64.to_le_bytes());
+    data.extend_from_slice(&2i64.to_le_bytes());
+    data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for (file_id, shard_index, slot_index) in entries {
+        data.extend_from_slice(file_id);
+        data.extend_from_slice(&[*shard_index, *slot_index]);
+    }
+    data.resize(UserVaultIndex::LEGACY_SIZE, 0);
+    data
+}
+
+/// A zero-copy shard with `records` in the leading slots
+pub fn zero_copy_shard(vault_master: Pubkey, owner: Pubkey, shard_index: u8, records: &[Pubkey]) -> Vec<u8> {
+    let bump = derive_vault_shard_pda(&vault_master, shard_index, &blockdrive::ID).1;
+    let mut shard: Box<UserVaultShard> = Box::new(bytemuck::Zeroable::zeroed());
+    shard.initialize(bump, vault_master, owner, shard_index, 1);
+    for record in records {
+        shard.add_file(*record, 2).unwrap();
+    }
+    [&UserVaultShard::DISCRIMINATOR[..], bytemuck::bytes_of(&*shard)].concat()
+}
+
+/// A zero-copy index page 0 holding `entries`
+pub fn zero_copy_index(vault_master: Pubkey, owner: Pubkey, entries: &[([u8; 16], u8, u8)]) -> Vec<u8> {
+    let bump = derive_vault_index_pda(&vault_master, &blockdrive::ID).1;
+    let mut index: Box<UserVaultIndex> = Box::new(bytemuck::Zeroable::zeroed());
+    index.initialize(bump, vault_master, owner, 0, 1);
+    for (file_id, shard_index, slot_index) in entries {
+        index.add_entry(*file_id, *shard_index, *slot_index, 2).unwrap();
+    }
+    [&UserVaultIndex::DISCRIMINATOR[..], bytemuck::bytes_of(&*index)].concat()
+}
+
+/// Seed a system-owned wallet
+pub fn add_wallet(program_test: &mut ProgramTest, address: Pubkey, lamports: u64) {
+    program_test.add_account(
+        address,
+        SolanaAccount {
+            lamports,
+            data: vec![],
+            owner: solana_sdk::system_program::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+/// Seed the global config with `pool` lamports above rent for cleanup tips
+pub fn add_config(program_test: &mut ProgramTest, config: VaultConfig, pool: u64) -> Pubkey {
+    let (address, bump) = Pubkey::find_program_address(&[VaultConfig::SEED_PREFIX], &blockdrive::ID);
+    add_program_account(
+        program_test,
+        address,
+        &VaultConfig { bump, ..config },
+        VaultConfig::SIZE,
+        rent_exempt(VaultConfig::SIZE) + pool,
+    );
+    address
+}
+
+pub fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: blockdrive::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Send `instructions` paid for by the context payer and signed by `signers`
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> std::result::Result<(), BanksClientError> {
+    let mut all_signers: Vec<&Keypair> = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+pub async fn lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .map(|account| account.lamports)
+        .unwrap_or(0)
+}
+
+/// Load and decode a program account, None once it is closed
+pub async fn fetch<T: AccountDeserialize>(
+    context: &mut ProgramTestContext,
+    address: Pubkey,
+) -> Option<T> {
+    let account = context.banks_client.get_account(address).await.unwrap()?;
+    T::try_deserialize(&mut account.data.as_slice()).ok()
+}
@@ -0,0 +1,234 @@
+//! Compute units of register_file_sharded and the index lookup on the zero-copy
+//! shard/index layout against the earlier Borsh layout
+//!
+//! Compute units are only meaningful for the SBF build, so these tests are
+//! ignored by default. They load two builds of the program:
+//!
+//! ```text
+//! cargo build-sbf                      # zero-copy: target/deploy/blockdrive.so
+//! # Borsh: build the commit before the zero-copy change the same way and copy
+//! # its blockdrive.so to tests/fixtures/blockdrive_borsh.so
+//! SBF_OUT_DIR=../../target/deploy cargo test --test compute_units -- --ignored --nocapture
+//! ```
+//!
+//! The Borsh build has no lookup instruction. Its find_entry runs inside
+//! register_file_sharded as the duplicate check, a linear scan over every entry
+//! since the new file_id is never found, so its register column includes it; the
+//! zero-copy build's lookup is measured through query_file_location
+
+mod common;
+
+use anchor_lang::prelude::*;
+use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, signature::Keypair,
+    signer::Signer as _, transaction::Transaction,
+};
+
+use blockdrive::instructions::sharding::{
+    derive_file_record_pda, derive_vault_index_pda, derive_vault_shard_pda,
+};
+use blockdrive::state::{FileRegistration, UserVaultMaster, VaultConfig, MAX_FILES_PER_SHARD};
+use common::*;
+
+/// Index fill levels to measure at (a page holds at most 1000 entries)
+const INDEX_ENTRIES: [usize; 3] = [0, 500, 999];
+
+#[derive(Clone, Copy, Debug)]
+enum Layout {
+    Borsh,
+    ZeroCopy,
+}
+
+struct Fixture {
+    owner: Keypair,
+    config: Pubkey,
+    fee_collector: Pubkey,
+    vault_master: Pubkey,
+    vault_shard: Pubkey,
+    vault_index: Pubkey,
+}
+
+fn file_id(n: usize) -> [u8; 16] {
+    let mut id = [0u8; 16];
+    id[..8].copy_from_slice(&(n as u64 + 1).to_be_bytes());
+    id
+}
+
+/// A Vault Master with shard 0 one file short of full and an index page
+/// holding `entries` files, all in `layout`
+async fn setup(layout: Layout, entries: usize) -> (ProgramTestContext, Fixture) {
+    let program_name = match layout {
+        Layout::Borsh => "blockdrive_borsh",
+        Layout::ZeroCopy => "blockdrive",
+    };
+    let mut program_test = ProgramTest::new(program_name, blockdrive::ID, None);
+    program_test.prefer_bpf(true);
+
+    let owner = Keypair::new();
+    add_wallet(&mut program_test, owner.pubkey(), 1_000_000_000);
+    let fee_collector = Pubkey::new_unique();
+    add_wallet(&mut program_test, fee_collector, 1_000_000_000);
+    let config = add_config(
+        &mut program_test,
+        VaultConfig {
+            fee_collector,
+            ..Default::default()
+        },
+        0,
+    );
+
+    // The Borsh build reads the leading fields of the current master layout
+    let (vault_master, bump) = Pubkey::find_program_address(
+        &[UserVaultMaster::SEED_PREFIX, owner.pubkey().as_ref()],
+        &blockdrive::ID,
+    );
+    let vault_shard = derive_vault_shard_pda(&vault_master, 0, &blockdrive::ID).0;
+    let mut shard_pointers = [Pubkey::default(); 10];
+    shard_pointers[0] = vault_shard;
+    add_program_account(
+        &mut program_test,
+        vault_master,
+        &UserVaultMaster {
+            bump,
+            owner: owner.pubkey(),
+            total_file_count: entries as u64,
+            total_shards: 1,
+            shard_pointers,
+            ..Default::default()
+        },
+        UserVaultMaster::SIZE,
+        rent_exempt(UserVaultMaster::SIZE),
+    );
+
+    let records: Vec<Pubkey> = (0..MAX_FILES_PER_SHARD - 1).map(|_| Pubkey::new_unique()).collect();
+    let index_entries: Vec<([u8; 16], u8, u8)> = (0..entries)
+        .map(|n| (file_id(n), (n / MAX_FILES_PER_SHARD) as u8, (n % MAX_FILES_PER_SHARD) as u8))
+        .collect();
+    let vault_index = derive_vault_index_pda(&vault_master, &blockdrive::ID).0;
+    let (shard_data, index_data) = match layout {
+        Layout::Borsh => (
+            legacy_shard(vault_master, owner.pubkey(), 0, &records),
+            legacy_index(vault_master, owner.pubkey(), &index_entries),
+        ),
+        Layout::ZeroCopy => (
+            zero_copy_shard(vault_master, owner.pubkey(), 0, &records),
+            zero_copy_index(vault_master, owner.pubkey(), &index_entries),
+        ),
+    };
+    add_raw_account(&mut program_test, vault_shard, shard_data);
+    add_raw_account(&mut program_test, vault_index, index_data);
+
+    let context = program_test.start_with_context().await;
+    (
+        context,
+        Fixture {
+            owner,
+            config,
+            fee_collector,
+            vault_master,
+            vault_shard,
+            vault_index,
+        },
+    )
+}
+
+fn register(fixture: &Fixture, file_id: [u8; 16]) -> Instruction {
+    let file_record = derive_file_record_pda(&fixture.vault_master, &file_id, &blockdrive::ID).0;
+    instruction(
+        blockdrive::accounts::RegisterFileSharded {
+            vault_master: fixture.vault_master,
+            vault_shard: fixture.vault_shard,
+            vault_index: fixture.vault_index,
+            file_record,
+            config: fixture.config,
+            fee_collector: fixture.fee_collector,
+            owner: fixture.owner.pubkey(),
+            system_program: solana_sdk::system_program::ID,
+        },
+        blockdrive::instruction::RegisterFileSharded {
+            file_id,
+            shard_index: 0,
+            registration: FileRegistration {
+                filename_hash: [1u8; 32],
+                file_size: 1_000,
+                encrypted_size: 1_100,
+                mime_type_hash: [2u8; 32],
+                security_level: 0,
+                encryption_commitment: [3u8; 32],
+                critical_bytes_commitment: [4u8; 32],
+                primary_cid: [5u8; 64],
+            },
+        },
+    )
+}
+
+fn query(fixture: &Fixture, file_id: [u8; 16]) -> Instruction {
+    instruction(
+        blockdrive::accounts::QueryFileLocation {
+            vault_master: fixture.vault_master,
+            vault_index: fixture.vault_index,
+        },
+        blockdrive::instruction::QueryFileLocation { file_id },
+    )
+}
+
+/// Compute units the program instruction consumed, and whether it succeeded
+/// The compute budget instruction in front of it costs a fixed 150 units
+async fn measure(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> (u64, bool) {
+    let mut all_signers: Vec<&Keypair> = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[ComputeBudgetInstruction::set_compute_unit_limit(1_400_000), instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    let outcome = context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    let metadata = outcome.metadata.expect("transaction was not executed");
+    (metadata.compute_units_consumed - 150, outcome.result.is_ok())
+}
+
+#[tokio::test]
+#[ignore = "needs the SBF builds described in the module docs"]
+async fn test_compute_units_by_layout() {
+    println!("{:>8} | {:>10} | {:>10} | {:>10}", "entries", "layout", "register", "lookup");
+
+    for entries in INDEX_ENTRIES {
+        let mut register_units = Vec::new();
+        for layout in [Layout::Borsh, Layout::ZeroCopy] {
+            let (mut context, fixture) = setup(layout, entries).await;
+
+            let (register, ok) =
+                measure(&mut context, register(&fixture, file_id(entries)), &[&fixture.owner]).await;
+            assert!(ok, "{layout:?} register_file_sharded failed at {entries} entries");
+
+            // Look up the file just registered, the last one in insertion order
+            let lookup = match layout {
+                Layout::Borsh => "-".to_string(),
+                Layout::ZeroCopy => {
+                    let (units, ok) = measure(&mut context, query(&fixture, file_id(entries)), &[]).await;
+                    assert!(ok, "query_file_location did not find the file at {entries} entries");
+                    units.to_string()
+                }
+            };
+
+            println!("{:>8} | {:>10} | {:>10} | {:>10}", entries, format!("{layout:?}"), register, lookup);
+            register_units.push(register);
+        }
+
+        assert!(
+            register_units[1] < register_units[0],
+            "zero-copy register_file_sharded is not cheaper at {entries} entries"
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Guardian recovery end to end: initialize_guardians, initiate/approve/execute
+//! or cancel_recovery, then sync_owner_records
+
+mod common;
+
+use anchor_lang::prelude::*;
+use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{instruction::AccountMeta, signature::Keypair, signer::Signer as _};
+
+use blockdrive::instructions::sharding::{derive_vault_index_pda, derive_vault_shard_pda};
+use blockdrive::state::{
+    Delegation, FileRecord, GuardianSet, PermissionLevel, RecoveryRequest, UserVault,
+    UserVaultIndex, UserVaultMaster, UserVaultShard, MIN_RECOVERY_DELAY,
+};
+use common::*;
+
+const FILE_ID: [u8; 16] = [7u8; 16];
+const NEW_COMMITMENT: [u8; 32] = [42u8; 32];
+
+struct Fixture {
+    owner: Keypair,
+    guardians: Vec<Keypair>,
+    new_owner: Keypair,
+    vault: Pubkey,
+    vault_master: Pubkey,
+    vault_shard: Pubkey,
+    vault_index: Pubkey,
+    file_record: Pubkey,
+    delegation: Pubkey,
+    guardian_set: Pubkey,
+    recovery_request: Pubkey,
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &blockdrive::ID)
+}
+
+/// An owner with both a UserVault holding one shared file and a Vault Master
+/// holding one shard and its index, plus three guardian wallets
+fn setup() -> (ProgramTest, Fixture) {
+    let mut program_test = program_test();
+    let owner = Keypair::new();
+    let new_owner = Keypair::new();
+    let guardians: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    for wallet in guardians.iter().chain([&owner, &new_owner]) {
+        add_wallet(&mut program_test, wallet.pubkey(), 1_000_000_000);
+    }
+
+    let (vault, vault_bump) = pda(&[UserVault::SEED_PREFIX, owner.pubkey().as_ref()]);
+    add_program_account(
+        &mut program_test,
+        vault,
+        &UserVault {
+            bump: vault_bump,
+            owner: owner.pubkey(),
+            master_key_commitment: [1u8; 32],
+            file_count: 1,
+            ..Default::default()
+        },
+        UserVault::SIZE,
+        rent_exempt(UserVault::SIZE),
+    );
+
+    let (file_record, record_bump) = pda(&[FileRecord::SEED_PREFIX, vault.as_ref(), &FILE_ID]);
+    add_program_account(
+        &mut program_test,
+        file_record,
+        &FileRecord {
+            bump: record_bump,
+            vault,
+            owner: owner.pubkey(),
+            file_id: FILE_ID,
+            is_shared: true,
+            delegation_count: 1,
+            ..Default::default()
+        },
+        FileRecord::SIZE,
+        rent_exempt(FileRecord::SIZE),
+    );
+
+    let grantee = Pubkey::new_unique();
+    let (delegation, delegation_bump) =
+        pda(&[Delegation::SEED_PREFIX, file_record.as_ref(), grantee.as_ref()]);
+    add_program_account(
+        &mut program_test,
+        delegation,
+        &Delegation {
+            bump: delegation_bump,
+            file_record,
+            grantor: owner.pubkey(),
+            grantee,
+            permission_level: PermissionLevel::Download,
+            is_active: true,
+            ..Default::default()
+        },
+        Delegation::SIZE,
+        rent_exempt(Delegation::SIZE),
+    );
+
+    let (vault_master, master_bump) = pda(&[UserVaultMaster::SEED_PREFIX, owner.pubkey().as_ref()]);
+    let vault_shard = derive_vault_shard_pda(&vault_master, 0, &blockdrive::ID).0;
+    let vault_index = derive_vault_index_pda(&vault_master, &blockdrive::ID).0;
+    let mut shard_pointers = [Pubkey::default(); 10];
+    shard_pointers[0] = vault_shard;
+    add_program_account(
+        &mut program_test,
+        vault_master,
+        &UserVaultMaster {
+            bump: master_bump,
+            owner: owner.pubkey(),
+            total_shards: 1,
+            shard_pointers,
+            master_key_commitment: [1u8; 32],
+            ..Default::default()
+        },
+        UserVaultMaster::SIZE,
+        rent_exempt(UserVaultMaster::SIZE),
+    );
+    add_raw_account(
+        &mut program_test,
+        vault_shard,
+        zero_copy_shard(vault_master, owner.pubkey(), 0, &[]),
+    );
+    add_raw_account(
+        &mut program_test,
+        vault_index,
+        zero_copy_index(vault_master, owner.pubkey(), &[]),
+    );
+
+    let guardian_set = pda(&[GuardianSet::SEED_PREFIX, owner.pubkey().as_ref()]).0;
+    let recovery_request = pda(&[RecoveryRequest::SEED_PREFIX, guardian_set.as_ref()]).0;
+
+    (
+        program_test,
+        Fixture {
+            owner,
+            guardians,
+            new_owner,
+            vault,
+            vault_master,
+            vault_shard,
+            vault_index,
+            file_record,
+            delegation,
+            guardian_set,
+            recovery_request,
+        },
+    )
+}
+
+/// Create a 2-of-3 guardian set and have the first guardian open a recovery
+async fn open_recovery(context: &mut ProgramTestContext, fixture: &Fixture) {
+    let initialize = instruction(
+        blockdrive::accounts::InitializeGuardians {
+            guardian_set: fixture.guardian_set,
+            vault: Some(fixture.vault),
+            vault_master: Some(fixture.vault_master),
+            owner: fixture.owner.pubkey(),
+            system_program: solana_sdk::system_program::ID,
+        },
+        blockdrive::instruction::InitializeGuardians {
+            vault_id: fixture.owner.pubkey(),
+            guardians: fixture.guardians.iter().map(|g| g.pubkey()).collect(),
+            threshold: 2,
+            recovery_delay: MIN_RECOVERY_DELAY,
+        },
+    );
+    send(context, &[initialize], &[&fixture.owner]).await.unwrap();
+
+    let initiate = instruction(
+        blockdrive::accounts::InitiateRecovery {
+            guardian_set: fixture.guardian_set,
+            recovery_request: fixture.recovery_request,
+            guardian: fixture.guardians[0].pubkey(),
+            system_program: solana_sdk::system_program::ID,
+        },
+        blockdrive::instruction::InitiateRecovery {
+            new_owner: fixture.new_owner.pubkey(),
+            new_master_key_commitment: NEW_COMMITMENT,
+        },
+    );
+    send(context, &[initiate], &[&fixture.guardians[0]]).await.unwrap();
+}
+
+fn approve(fixture: &Fixture, guardian: &Keypair) -> solana_sdk::instruction::Instruction {
+    instruction(
+        blockdrive::accounts::ApproveRecovery {
+            guardian_set: fixture.guardian_set,
+            recovery_request: fixture.recovery_request,
+            guardian: guardian.pubkey(),
+        },
+        blockdrive::instruction::ApproveRecovery {},
+    )
+}
+
+fn execute(fixture: &Fixture) -> solana_sdk::instruction::Instruction {
+    instruction(
+        blockdrive::accounts::ExecuteRecovery {
+            guardian_set: fixture.guardian_set,
+            recovery_request: fixture.recovery_request,
+            vault: fixture.vault,
+            vault_master: fixture.vault_master,
+            initiator: fixture.guardians[0].pubkey(),
+            new_owner: fixture.new_owner.pubkey(),
+        },
+        blockdrive::instruction::ExecuteRecovery {},
+    )
+}
+
+async fn advance_clock(context: &mut ProgramTestContext, seconds: i64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += seconds;
+    context.set_sysvar(&clock);
+    context.get_new_latest_blockhash().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_recovery_moves_vaults_and_records() {
+    let (program_test, fixture) = setup();
+    let mut context = program_test.start_with_context().await;
+    open_recovery(&mut context, &fixture).await;
+
+    // One approval of two: not executable, and a guardian cannot approve twice
+    assert!(send(&mut context, &[execute(&fixture)], &[&fixture.new_owner]).await.is_err());
+    assert!(send(&mut context, &[approve(&fixture, &fixture.guardians[0])], &[&fixture.guardians[0]])
+        .await
+        .is_err());
+
+    // Threshold reached: the timelock starts
+    send(&mut context, &[approve(&fixture, &fixture.guardians[1])], &[&fixture.guardians[1]])
+        .await
+        .unwrap();
+    let request: RecoveryRequest = fetch(&mut context, fixture.recovery_request).await.unwrap();
+    assert_eq!(request.approval_count, 2);
+    assert_eq!(request.executable_at, request.initiated_at + MIN_RECOVERY_DELAY);
+
+    // A fresh blockhash keeps the repeated execute from being deduplicated
+    context.get_new_latest_blockhash().await.unwrap();
+    assert!(send(&mut context, &[execute(&fixture)], &[&fixture.new_owner]).await.is_err());
+
+    advance_clock(&mut context, MIN_RECOVERY_DELAY).await;
+    let initiator_before = lamports(&mut context, fixture.guardians[0].pubkey()).await;
+    send(&mut context, &[execute(&fixture)], &[&fixture.new_owner]).await.unwrap();
+
+    // The request's rent goes back to the guardian that opened it
+    assert_eq!(
+        lamports(&mut context, fixture.guardians[0].pubkey()).await,
+        initiator_before + rent_exempt(RecoveryRequest::SIZE)
+    );
+    assert!(fetch::<RecoveryRequest>(&mut context, fixture.recovery_request).await.is_none());
+
+    let guardian_set: GuardianSet = fetch(&mut context, fixture.guardian_set).await.unwrap();
+    assert_eq!(guardian_set.owner, fixture.new_owner.pubkey());
+    assert!(!guardian_set.pending_recovery);
+
+    // Both vault accounts move, keep their seed and rotate the key
+    let vault: UserVault = fetch(&mut context, fixture.vault).await.unwrap();
+    assert_eq!(vault.owner, fixture.new_owner.pubkey());
+    assert_eq!(vault.vault_seed(), fixture.owner.pubkey());
+    assert_eq!(vault.master_key_commitment, NEW_COMMITMENT);
+    assert_eq!(vault.key_epoch, 1);
+    let vault_master: UserVaultMaster = fetch(&mut context, fixture.vault_master).await.unwrap();
+    assert_eq!(vault_master.owner, fixture.new_owner.pubkey());
+    assert_eq!(vault_master.vault_seed(), fixture.owner.pubkey());
+    assert_eq!(vault_master.master_key_commitment, NEW_COMMITMENT);
+
+    // The new owner re-points the records
+    let mut sync = instruction(
+        blockdrive::accounts::SyncOwnerRecords {
+            vault: Some(fixture.vault),
+            vault_master: Some(fixture.vault_master),
+            owner: fixture.new_owner.pubkey(),
+        },
+        blockdrive::instruction::SyncOwnerRecords {},
+    );
+    for record in [
+        fixture.file_record,
+        fixture.delegation,
+        fixture.vault_shard,
+        fixture.vault_index,
+    ] {
+        sync.accounts.push(AccountMeta::new(record, false));
+    }
+    send(&mut context, &[sync], &[&fixture.new_owner]).await.unwrap();
+
+    let file_record: FileRecord = fetch(&mut context, fixture.file_record).await.unwrap();
+    assert_eq!(file_record.owner, fixture.new_owner.pubkey());
+    let delegation: Delegation = fetch(&mut context, fixture.delegation).await.unwrap();
+    assert_eq!(delegation.grantor, fixture.new_owner.pubkey());
+
+    let shard = context.banks_client.get_account(fixture.vault_shard).await.unwrap().unwrap();
+    let shard: &UserVaultShard = bytemuck::from_bytes(&shard.data[8..]);
+    assert_eq!(shard.owner, fixture.new_owner.pubkey());
+    let index = context.banks_client.get_account(fixture.vault_index).await.unwrap().unwrap();
+    let index: &UserVaultIndex = bytemuck::from_bytes(&index.data[8..]);
+    assert_eq!(index.owner, fixture.new_owner.pubkey());
+}
+
+#[tokio::test]
+async fn test_owner_cancels_pending_recovery() {
+    let (program_test, fixture) = setup();
+    let mut context = program_test.start_with_context().await;
+    open_recovery(&mut context, &fixture).await;
+
+    // Guardian settings are locked while the recovery is pending
+    let update = instruction(
+        blockdrive::accounts::UpdateGuardians {
+            guardian_set: fixture.guardian_set,
+            vault: fixture.vault,
+            vault_master: fixture.vault_master,
+            owner: fixture.owner.pubkey(),
+        },
+        blockdrive::instruction::UpdateGuardians {
+            guardians: vec![fixture.guardians[0].pubkey()],
+            threshold: 1,
+            recovery_delay: MIN_RECOVERY_DELAY,
+        },
+    );
+    assert!(send(&mut context, std::slice::from_ref(&update), &[&fixture.owner]).await.is_err());
+
+    let initiator_before = lamports(&mut context, fixture.guardians[0].pubkey()).await;
+    let cancel = instruction(
+        blockdrive::accounts::CancelRecovery {
+            guardian_set: fixture.guardian_set,
+            recovery_request: fixture.recovery_request,
+            vault: fixture.vault,
+            vault_master: fixture.vault_master,
+            initiator: fixture.guardians[0].pubkey(),
+            owner: fixture.owner.pubkey(),
+        },
+        blockdrive::instruction::CancelRecovery {},
+    );
+    send(&mut context, &[cancel], &[&fixture.owner]).await.unwrap();
+
+    assert_eq!(
+        lamports(&mut context, fixture.guardians[0].pubkey()).await,
+        initiator_before + rent_exempt(RecoveryRequest::SIZE)
+    );
+    assert!(fetch::<RecoveryRequest>(&mut context, fixture.recovery_request).await.is_none());
+    let guardian_set: GuardianSet = fetch(&mut context, fixture.guardian_set).await.unwrap();
+    assert_eq!(guardian_set.owner, fixture.owner.pubkey());
+    assert!(!guardian_set.pending_recovery);
+
+    // The vault stays with the owner, who can change guardians again
+    let vault: UserVault = fetch(&mut context, fixture.vault).await.unwrap();
+    assert_eq!(vault.owner, fixture.owner.pubkey());
+    advance_clock(&mut context, 1).await;
+    send(&mut context, &[update], &[&fixture.owner]).await.unwrap();
+}
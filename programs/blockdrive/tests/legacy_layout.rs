@@ -0,0 +1,200 @@
+//! migrate_shard_layout / migrate_index_layout and the close paths on shards and
+//! indexes still in the pre-zero-copy Borsh layout
+
+mod common;
+
+use anchor_lang::prelude::*;
+use solana_program_test::{tokio, ProgramTest};
+use solana_sdk::{signature::Keypair, signer::Signer as _};
+
+use blockdrive::instructions::sharding::{derive_vault_index_pda, derive_vault_shard_pda};
+use blockdrive::state::{UserVaultIndex, UserVaultMaster, UserVaultShard};
+use common::*;
+
+struct Fixture {
+    owner: Keypair,
+    vault_master: Pubkey,
+    vault_index: Pubkey,
+    shards: Vec<Pubkey>,
+}
+
+fn file_id(n: u8) -> [u8; 16] {
+    [n; 16]
+}
+
+/// A Vault Master whose shards and index predate the zero-copy layout
+/// Shard 0 is empty; shard 1 holds `files`, which the index lists
+fn setup(files: &[[u8; 16]]) -> (ProgramTest, Fixture) {
+    let mut program_test = program_test();
+    let owner = Keypair::new();
+    add_wallet(&mut program_test, owner.pubkey(), 1_000_000_000);
+
+    let (vault_master, bump) = Pubkey::find_program_address(
+        &[UserVaultMaster::SEED_PREFIX, owner.pubkey().as_ref()],
+        &blockdrive::ID,
+    );
+    let shards: Vec<Pubkey> = (0..2)
+        .map(|index| derive_vault_shard_pda(&vault_master, index, &blockdrive::ID).0)
+        .collect();
+    let mut shard_pointers = [Pubkey::default(); 10];
+    shard_pointers[..2].copy_from_slice(&shards);
+    add_program_account(
+        &mut program_test,
+        vault_master,
+        &UserVaultMaster {
+            bump,
+            owner: owner.pubkey(),
+            total_file_count: files.len() as u64,
+            total_shards: 2,
+            active_shard_index: 1,
+            shard_pointers,
+            ..Default::default()
+        },
+        UserVaultMaster::SIZE,
+        rent_exempt(UserVaultMaster::SIZE),
+    );
+
+    let records: Vec<Pubkey> = files.iter().map(|_| Pubkey::new_unique()).collect();
+    add_raw_account(
+        &mut program_test,
+        shards[0],
+        legacy_shard(vault_master, owner.pubkey(), 0, &[]),
+    );
+    add_raw_account(
+        &mut program_test,
+        shards[1],
+        legacy_shard(vault_master, owner.pubkey(), 1, &records),
+    );
+
+    let vault_index = derive_vault_index_pda(&vault_master, &blockdrive::ID).0;
+    let entries: Vec<([u8; 16], u8, u8)> = files
+        .iter()
+        .enumerate()
+        .map(|(slot, file_id)| (*file_id, 1, slot as u8))
+        .collect();
+    add_raw_account(
+        &mut program_test,
+        vault_index,
+        legacy_index(vault_master, owner.pubkey(), &entries),
+    );
+
+    (
+        program_test,
+        Fixture {
+            owner,
+            vault_master,
+            vault_index,
+            shards,
+        },
+    )
+}
+
+fn query(fixture: &Fixture, file_id: [u8; 16]) -> solana_sdk::instruction::Instruction {
+    instruction(
+        blockdrive::accounts::QueryFileLocation {
+            vault_master: fixture.vault_master,
+            vault_index: fixture.vault_index,
+        },
+        blockdrive::instruction::QueryFileLocation { file_id },
+    )
+}
+
+#[tokio::test]
+async fn test_migrated_layouts_load_as_zero_copy() {
+    let files = [file_id(9), file_id(2), file_id(5)];
+    let (program_test, fixture) = setup(&files);
+    let mut context = program_test.start_with_context().await;
+
+    // The Borsh layout is refused until it is converted
+    assert!(send(&mut context, &[query(&fixture, file_id(9))], &[]).await.is_err());
+
+    let migrate = vec![
+        instruction(
+            blockdrive::accounts::MigrateIndexLayout {
+                vault_index: fixture.vault_index,
+            },
+            blockdrive::instruction::MigrateIndexLayout {},
+        ),
+        instruction(
+            blockdrive::accounts::MigrateShardLayout {
+                vault_shard: fixture.shards[1],
+            },
+            blockdrive::instruction::MigrateShardLayout {},
+        ),
+    ];
+    send(&mut context, &migrate, &[]).await.unwrap();
+    send(&mut context, &[query(&fixture, file_id(2))], &[]).await.unwrap();
+
+    let index = context
+        .banks_client
+        .get_account(fixture.vault_index)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(index.data.len(), UserVaultIndex::MAX_SIZE);
+    let index: &UserVaultIndex = bytemuck::from_bytes(&index.data[8..]);
+    assert_eq!(index.vault_master, fixture.vault_master);
+    assert_eq!(index.entry_count, 3);
+    assert_eq!(index.find_entry(&file_id(9)), Some((1, 0)));
+    assert_eq!(index.find_entry(&file_id(5)), Some((1, 2)));
+
+    let shard = context
+        .banks_client
+        .get_account(fixture.shards[1])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(shard.data.len(), UserVaultShard::SIZE);
+    let shard: &UserVaultShard = bytemuck::from_bytes(&shard.data[8..]);
+    assert_eq!(shard.owner, fixture.owner.pubkey());
+    assert_eq!((shard.shard_index, shard.file_count), (1, 3));
+    assert!(shard.get_file(2).is_some());
+
+    // Converting again is a no-op
+    send(&mut context, &migrate[1..], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_close_legacy_shards_and_index() {
+    let (program_test, fixture) = setup(&[]);
+    let mut context = program_test.start_with_context().await;
+    let owner_before = lamports(&mut context, fixture.owner.pubkey()).await;
+
+    let close_shards: Vec<_> = fixture
+        .shards
+        .iter()
+        .map(|vault_shard| {
+            instruction(
+                blockdrive::accounts::CloseShard {
+                    vault_master: fixture.vault_master,
+                    vault_shard: *vault_shard,
+                    vault_directory: None,
+                    owner: fixture.owner.pubkey(),
+                },
+                blockdrive::instruction::CloseShard {},
+            )
+        })
+        .collect();
+    let close_master = instruction(
+        blockdrive::accounts::CloseVaultMaster {
+            vault_master: fixture.vault_master,
+            vault_index: fixture.vault_index,
+            owner: fixture.owner.pubkey(),
+        },
+        blockdrive::instruction::CloseVaultMaster {},
+    );
+    let mut instructions = close_shards;
+    instructions.push(close_master);
+    send(&mut context, &instructions, &[&fixture.owner]).await.unwrap();
+
+    let reclaimed = 2 * rent_exempt(UserVaultShard::LEGACY_SIZE)
+        + rent_exempt(UserVaultIndex::LEGACY_SIZE)
+        + rent_exempt(UserVaultMaster::SIZE);
+    assert_eq!(
+        lamports(&mut context, fixture.owner.pubkey()).await,
+        owner_before + reclaimed
+    );
+    for address in fixture.shards.iter().chain([&fixture.vault_index, &fixture.vault_master]) {
+        assert_eq!(lamports(&mut context, *address).await, 0);
+    }
+}
@@ -0,0 +1,365 @@
+//! migrate_legacy_vault / migrate_legacy_file / finalize_legacy_migration end to end
+
+mod common;
+
+use anchor_lang::prelude::*;
+use solana_program_test::tokio;
+use solana_sdk::{instruction::Instruction, signature::Keypair, signer::Signer as _};
+
+use blockdrive::instructions::sharding::{derive_vault_index_pda, derive_vault_shard_pda};
+use blockdrive::state::{
+    Delegation, FileRecord, FileVersion, PermissionLevel, UserVault, UserVaultIndex,
+    UserVaultMaster, UserVaultShard, VaultConfig,
+};
+use common::*;
+
+const FILE_ID: [u8; 16] = [9u8; 16];
+const FILE_SIZE: u64 = 1_000;
+
+struct Fixture {
+    owner: Keypair,
+    config: Pubkey,
+    vault: Pubkey,
+    legacy_file_record: Pubkey,
+    grantee: Pubkey,
+    resharee: Pubkey,
+    legacy_grant: Pubkey,
+    legacy_reshare: Pubkey,
+    legacy_version: Pubkey,
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &blockdrive::ID)
+}
+
+/// A legacy vault with one file that is shared (owner -> grantee -> resharee)
+/// and has one retained version
+fn setup(paused: bool) -> (solana_program_test::ProgramTest, Fixture) {
+    let mut program_test = program_test();
+    let owner = Keypair::new();
+    add_wallet(&mut program_test, owner.pubkey(), 10_000_000_000);
+
+    let config = add_config(
+        &mut program_test,
+        VaultConfig {
+            is_paused: paused,
+            ..Default::default()
+        },
+        0,
+    );
+
+    let (vault, vault_bump) = pda(&[UserVault::SEED_PREFIX, owner.pubkey().as_ref()]);
+    add_program_account(
+        &mut program_test,
+        vault,
+        &UserVault {
+            bump: vault_bump,
+            owner: owner.pubkey(),
+            file_count: 1,
+            total_storage: FILE_SIZE,
+            ..Default::default()
+        },
+        UserVault::SIZE,
+        rent_exempt(UserVault::SIZE),
+    );
+
+    let (legacy_file_record, record_bump) =
+        pda(&[FileRecord::SEED_PREFIX, vault.as_ref(), &FILE_ID]);
+    add_program_account(
+        &mut program_test,
+        legacy_file_record,
+        &FileRecord {
+            bump: record_bump,
+            vault,
+            owner: owner.pubkey(),
+            file_id: FILE_ID,
+            file_size: FILE_SIZE,
+            is_shared: true,
+            delegation_count: 2,
+            version: 1,
+            retained_versions: 1,
+            ..Default::default()
+        },
+        FileRecord::SIZE,
+        rent_exempt(FileRecord::SIZE),
+    );
+
+    let grantee = Pubkey::new_unique();
+    let resharee = Pubkey::new_unique();
+    let (legacy_grant, grant_bump) = pda(&[
+        Delegation::SEED_PREFIX,
+        legacy_file_record.as_ref(),
+        grantee.as_ref(),
+    ]);
+    add_program_account(
+        &mut program_test,
+        legacy_grant,
+        &Delegation {
+            bump: grant_bump,
+            file_record: legacy_file_record,
+            grantor: owner.pubkey(),
+            grantee,
+            permission_level: PermissionLevel::Reshare,
+            created_at: 1,
+            is_active: true,
+            ..Default::default()
+        },
+        Delegation::SIZE,
+        rent_exempt(Delegation::SIZE),
+    );
+    let (legacy_reshare, reshare_bump) = pda(&[
+        Delegation::SEED_PREFIX,
+        legacy_file_record.as_ref(),
+        resharee.as_ref(),
+    ]);
+    add_program_account(
+        &mut program_test,
+        legacy_reshare,
+        &Delegation {
+            bump: reshare_bump,
+            file_record: legacy_file_record,
+            grantor: grantee,
+            grantee: resharee,
+            permission_level: PermissionLevel::View,
+            created_at: 2,
+            is_active: true,
+            depth: 1,
+            parent_delegation: legacy_grant,
+            ..Default::default()
+        },
+        Delegation::SIZE,
+        rent_exempt(Delegation::SIZE),
+    );
+
+    let (legacy_version, version_bump) = pda(&[
+        FileVersion::SEED_PREFIX,
+        legacy_file_record.as_ref(),
+        &0u32.to_le_bytes(),
+    ]);
+    add_program_account(
+        &mut program_test,
+        legacy_version,
+        &FileVersion {
+            bump: version_bump,
+            file_record: legacy_file_record,
+            version: 0,
+            file_size: FILE_SIZE,
+            encrypted_size: FILE_SIZE,
+            encryption_commitment: [1u8; 32],
+            critical_bytes_commitment: [2u8; 32],
+            primary_cid: [3u8; 64],
+            redundancy_cid: [0u8; 64],
+            key_epoch: 0,
+            superseded_at: 5,
+            reserved: [0u8; 32],
+        },
+        FileVersion::SIZE,
+        rent_exempt(FileVersion::SIZE),
+    );
+
+    (
+        program_test,
+        Fixture {
+            owner,
+            config,
+            vault,
+            legacy_file_record,
+            grantee,
+            resharee,
+            legacy_grant,
+            legacy_reshare,
+            legacy_version,
+        },
+    )
+}
+
+fn vault_master(fixture: &Fixture) -> Pubkey {
+    pda(&[UserVaultMaster::SEED_PREFIX, fixture.owner.pubkey().as_ref()]).0
+}
+
+/// migrate_legacy_vault, then finish the index and open shard 0
+fn start_migration(fixture: &Fixture) -> Vec<Instruction> {
+    let vault_master = vault_master(fixture);
+    let (vault_index, _) = derive_vault_index_pda(&vault_master, &blockdrive::ID);
+    let (vault_shard, _) = derive_vault_shard_pda(&vault_master, 0, &blockdrive::ID);
+    vec![
+        instruction(
+            blockdrive::accounts::MigrateLegacyVault {
+                vault: fixture.vault,
+                vault_master,
+                vault_index,
+                config: fixture.config,
+                owner: fixture.owner.pubkey(),
+                system_program: solana_sdk::system_program::ID,
+            },
+            blockdrive::instruction::MigrateLegacyVault {},
+        ),
+        instruction(
+            blockdrive::accounts::ExtendVaultIndex {
+                vault_master,
+                vault_index,
+                owner: fixture.owner.pubkey(),
+            },
+            blockdrive::instruction::ExtendVaultIndex { page_index: 0 },
+        ),
+        instruction(
+            blockdrive::accounts::CreateShard {
+                vault_master,
+                vault_shard,
+                vault_directory: None,
+                owner: fixture.owner.pubkey(),
+                system_program: solana_sdk::system_program::ID,
+            },
+            blockdrive::instruction::CreateShard { shard_index: 0 },
+        ),
+    ]
+}
+
+fn migrate_file(fixture: &Fixture, links: &[(Pubkey, Pubkey)]) -> Instruction {
+    let vault_master = vault_master(fixture);
+    let (file_record, _) = pda(&[FileRecord::SEED_PREFIX, vault_master.as_ref(), &FILE_ID]);
+    let mut ix = instruction(
+        blockdrive::accounts::MigrateLegacyFile {
+            vault: fixture.vault,
+            legacy_file_record: fixture.legacy_file_record,
+            vault_master,
+            vault_shard: derive_vault_shard_pda(&vault_master, 0, &blockdrive::ID).0,
+            vault_index: derive_vault_index_pda(&vault_master, &blockdrive::ID).0,
+            file_record,
+            config: fixture.config,
+            owner: fixture.owner.pubkey(),
+            system_program: solana_sdk::system_program::ID,
+        },
+        blockdrive::instruction::MigrateLegacyFile {},
+    );
+    for (legacy, target) in links {
+        ix.accounts
+            .push(solana_sdk::instruction::AccountMeta::new(*legacy, false));
+        ix.accounts
+            .push(solana_sdk::instruction::AccountMeta::new(*target, false));
+    }
+    ix
+}
+
+#[tokio::test]
+async fn test_migration_moves_shares_and_versions() {
+    let (program_test, fixture) = setup(false);
+    let mut context = program_test.start_with_context().await;
+
+    send(&mut context, &start_migration(&fixture), &[&fixture.owner])
+        .await
+        .unwrap();
+
+    let vault_master = vault_master(&fixture);
+    let (file_record, _) = pda(&[FileRecord::SEED_PREFIX, vault_master.as_ref(), &FILE_ID]);
+    let new_grant = pda(&[Delegation::SEED_PREFIX, file_record.as_ref(), fixture.grantee.as_ref()]).0;
+    let new_reshare =
+        pda(&[Delegation::SEED_PREFIX, file_record.as_ref(), fixture.resharee.as_ref()]).0;
+    let new_version = pda(&[FileVersion::SEED_PREFIX, file_record.as_ref(), &0u32.to_le_bytes()]).0;
+
+    // Every delegation and version has to come along
+    assert!(send(
+        &mut context,
+        &[migrate_file(&fixture, &[(fixture.legacy_grant, new_grant)])],
+        &[&fixture.owner],
+    )
+    .await
+    .is_err());
+
+    let owner_before = lamports(&mut context, fixture.owner.pubkey()).await;
+    send(
+        &mut context,
+        &[migrate_file(
+            &fixture,
+            &[
+                (fixture.legacy_reshare, new_reshare),
+                (fixture.legacy_grant, new_grant),
+                (fixture.legacy_version, new_version),
+            ],
+        )],
+        &[&fixture.owner],
+    )
+    .await
+    .unwrap();
+
+    // The owner funded the copies out of the legacy refunds and the record's rent
+    // moved across, so it is only out the new record's rent minus the old one's
+    assert_eq!(lamports(&mut context, fixture.owner.pubkey()).await, owner_before);
+
+    for legacy in [
+        fixture.legacy_file_record,
+        fixture.legacy_grant,
+        fixture.legacy_reshare,
+        fixture.legacy_version,
+    ] {
+        assert_eq!(lamports(&mut context, legacy).await, 0);
+    }
+
+    let record: FileRecord = fetch(&mut context, file_record).await.unwrap();
+    assert_eq!(record.vault, vault_master);
+    assert_eq!(record.delegation_count, 2);
+    assert_eq!(record.retained_versions, 1);
+
+    let grant: Delegation = fetch(&mut context, new_grant).await.unwrap();
+    assert_eq!(grant.file_record, file_record);
+    assert_eq!(grant.grantor, fixture.owner.pubkey());
+    assert_eq!(grant.parent_delegation, Pubkey::default());
+
+    let reshare: Delegation = fetch(&mut context, new_reshare).await.unwrap();
+    assert_eq!(reshare.file_record, file_record);
+    assert_eq!(reshare.grantor, fixture.grantee);
+    assert_eq!(reshare.depth, 1);
+    assert_eq!(reshare.parent_delegation, new_grant);
+
+    let version: FileVersion = fetch(&mut context, new_version).await.unwrap();
+    assert_eq!(version.file_record, file_record);
+    assert_eq!(version.primary_cid, [3u8; 64]);
+
+    let shard = context
+        .banks_client
+        .get_account(derive_vault_shard_pda(&vault_master, 0, &blockdrive::ID).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let shard: &UserVaultShard = bytemuck::from_bytes(&shard.data[8..UserVaultShard::SIZE]);
+    assert_eq!(shard.file_records[0], file_record);
+
+    let index = context
+        .banks_client
+        .get_account(derive_vault_index_pda(&vault_master, &blockdrive::ID).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let index: &UserVaultIndex = bytemuck::from_bytes(&index.data[8..UserVaultIndex::MAX_SIZE]);
+    assert_eq!(index.find_entry(&FILE_ID), Some((0, 0)));
+
+    // The emptied vault can now be closed into its own master
+    send(
+        &mut context,
+        &[instruction(
+            blockdrive::accounts::FinalizeLegacyMigration {
+                vault: fixture.vault,
+                vault_master,
+                owner: fixture.owner.pubkey(),
+            },
+            blockdrive::instruction::FinalizeLegacyMigration {},
+        )],
+        &[&fixture.owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(lamports(&mut context, fixture.vault).await, 0);
+}
+
+#[tokio::test]
+async fn test_migration_blocked_while_paused() {
+    let (program_test, fixture) = setup(true);
+    let mut context = program_test.start_with_context().await;
+
+    assert!(send(&mut context, &start_migration(&fixture), &[&fixture.owner])
+        .await
+        .is_err());
+    assert!(fetch::<UserVaultMaster>(&mut context, vault_master(&fixture))
+        .await
+        .is_none());
+}
@@ -556,8 +556,14 @@ export class ShardingClient {
 
   private parseVaultShard(pubkey: PublicKey, data: Buffer): ParsedVaultShard | null {
     try {
-      // Skip 8-byte discriminator + 1-byte bump
-      let offset = 9;
+      // Zero-copy layout: skip 8-byte discriminator
+      let offset = 8;
+
+      const createdAt = data.readBigInt64LE(offset);
+      offset += 8;
+
+      const updatedAt = data.readBigInt64LE(offset);
+      offset += 8;
 
       const vaultMaster = new PublicKey(data.slice(offset, offset + 32));
       offset += 32;
@@ -565,15 +571,6 @@ export class ShardingClient {
       const owner = new PublicKey(data.slice(offset, offset + 32));
       offset += 32;
 
-      const shardIndex = data.readUInt8(offset);
-      offset += 1;
-
-      const fileCount = data.readUInt8(offset);
-      offset += 1;
-
-      const status = data.readUInt8(offset);
-      offset += 1;
-
       // Parse file records (100 * 32 = 3200 bytes)
       const fileRecords: string[] = [];
       for (let i = 0; i < 100; i++) {
@@ -584,10 +581,16 @@ export class ShardingClient {
         }
       }
 
-      const createdAt = data.readBigInt64LE(offset);
-      offset += 8;
+      // Skip 1-byte bump
+      offset += 1;
 
-      const updatedAt = data.readBigInt64LE(offset);
+      const shardIndex = data.readUInt8(offset);
+      offset += 1;
+
+      const fileCount = data.readUInt8(offset);
+      offset += 1;
+
+      const status = data.readUInt8(offset);
 
       return {
         publicKey: pubkey,
@@ -609,8 +612,14 @@ export class ShardingClient {
 
   private parseVaultIndex(pubkey: PublicKey, data: Buffer): ParsedVaultIndex | null {
     try {
-      // Skip 8-byte discriminator + 1-byte bump
-      let offset = 9;
+      // Zero-copy layout: skip 8-byte discriminator
+      let offset = 8;
+
+      const createdAt = data.readBigInt64LE(offset);
+      offset += 8;
+
+      const updatedAt = data.readBigInt64LE(offset);
+      offset += 8;
 
       const vaultMaster = new PublicKey(data.slice(offset, offset + 32));
       offset += 32;
@@ -621,18 +630,12 @@ export class ShardingClient {
       const entryCount = data.readUInt16LE(offset);
       offset += 2;
 
-      const createdAt = data.readBigInt64LE(offset);
-      offset += 8;
-
-      const updatedAt = data.readBigInt64LE(offset);
-      offset += 8;
-
-      // Parse entries (vec length + entries)
-      const vecLen = data.readUInt32LE(offset);
-      offset += 4;
+      // Skip bump (1) + page_index (1) + reserved (28)
+      offset += 30;
 
+      // Parse the first entryCount entries of the fixed array
       const entries: { fileId: string; shardIndex: number; slotIndex: number }[] = [];
-      for (let i = 0; i < vecLen && i < entryCount; i++) {
+      for (let i = 0; i < entryCount; i++) {
         const fileId = data.slice(offset, offset + 16);
         offset += 16;
         const shardIndex = data.readUInt8(offset);